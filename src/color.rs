@@ -1,6 +1,346 @@
-use std::fmt::Display;
+use std::{error::Error, fmt::Display, ops::Range};
 
-use crate::{colors::Colors, misc::process_percent};
+use crate::{
+    color_space::{
+        channels_to_linear_srgb, lab_to_linear_srgb, linear_srgb_to_channels, linear_srgb_to_lab,
+        linear_srgb_to_oklab, linear_tables, oklab_to_linear_srgb, polar_to_rectangular,
+        rectangular_to_polar,
+    },
+    colors::Colors,
+    misc::process_percent,
+};
+
+const COLOR_FORMATS: &str = "a color name, #RRGGBBAA, #RRGGBB, #RGBA, #RGB, \
+    rgb(r g b [/ a]), rgb(r, g, b[, a]), hsl(h s l [/ a]), hsl(h, s, l[, a]), \
+    hwb(h w b [/ a]), lab(l a b [/ a]), lch(l c h [/ a]), oklab(l a b [/ a]) or oklch(l c h [/ a])";
+
+///why Color::from_html or Color::from_name rejected their input, spans are byte ranges into the untrimmed input
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum ColorParseError {
+    UnknownName {
+        input: String,
+        span: Range<usize>,
+        expected: &'static str,
+    },
+    //not a hexadecimal number of a supported length
+    InvalidHex {
+        input: String,
+        span: Range<usize>,
+        expected: &'static str,
+    },
+    //missing parentheses or the wrong number of arguments
+    InvalidFunction {
+        input: String,
+        span: Range<usize>,
+        expected: &'static str,
+    },
+    //a single function argument out of range or not a number
+    InvalidComponent {
+        input: String,
+        span: Range<usize>,
+        expected: &'static str,
+    },
+    UnknownFormat {
+        input: String,
+        span: Range<usize>,
+        expected: &'static str,
+    },
+}
+
+impl ColorParseError {
+    pub fn input(&self) -> &str {
+        match self {
+            ColorParseError::UnknownName { input, .. }
+            | ColorParseError::InvalidHex { input, .. }
+            | ColorParseError::InvalidFunction { input, .. }
+            | ColorParseError::InvalidComponent { input, .. }
+            | ColorParseError::UnknownFormat { input, .. } => input,
+        }
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            ColorParseError::UnknownName { span, .. }
+            | ColorParseError::InvalidHex { span, .. }
+            | ColorParseError::InvalidFunction { span, .. }
+            | ColorParseError::InvalidComponent { span, .. }
+            | ColorParseError::UnknownFormat { span, .. } => span.clone(),
+        }
+    }
+
+    pub fn expected(&self) -> &'static str {
+        match self {
+            ColorParseError::UnknownName { expected, .. }
+            | ColorParseError::InvalidHex { expected, .. }
+            | ColorParseError::InvalidFunction { expected, .. }
+            | ColorParseError::InvalidComponent { expected, .. }
+            | ColorParseError::UnknownFormat { expected, .. } => expected,
+        }
+    }
+}
+
+impl Display for ColorParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let span = self.span();
+        let problem = match self {
+            ColorParseError::UnknownName { .. } => "unknown color name",
+            ColorParseError::InvalidHex { .. } => "invalid hexadecimal color",
+            ColorParseError::InvalidFunction { .. } => "invalid color function",
+            ColorParseError::InvalidComponent { .. } => "invalid color component",
+            ColorParseError::UnknownFormat { .. } => "unknown color format",
+        };
+        let found = match &self.input()[span.clone()] {
+            "" => "end of input",
+            found => found,
+        };
+        write!(
+            f,
+            "{} in color \"{}\" at {}..{}: found \"{}\", expected {}",
+            problem,
+            self.input(),
+            span.start,
+            span.end,
+            found,
+            self.expected()
+        )
+    }
+}
+
+impl Error for ColorParseError {}
+
+type Argument<'a> = (&'a str, Range<usize>);
+
+//splits the arguments of a color function into three components and an optional alpha,
+//html is what follows the function name and starts at byte start of input,
+//legacy functions may separate them with commas, otherwise spaces and a slash before alpha
+fn color_arguments<'a>(
+    input: &str,
+    html: &'a str,
+    start: usize,
+    legacy: bool,
+    expected: &'static str,
+) -> Result<(Vec<Argument<'a>>, Option<Argument<'a>>), ColorParseError> {
+    let leading = html.len() - html.trim_start().len();
+    let inner = html.trim();
+    let invalid = || ColorParseError::InvalidFunction {
+        input: input.to_string(),
+        span: start + leading..start + leading + inner.len(),
+        expected,
+    };
+    if !(inner.len() >= 2 && inner.starts_with('(') && inner.ends_with(')')) {
+        return Err(invalid());
+    }
+    let inner_start = start + leading + 1;
+    let inner = &inner[1..inner.len() - 1];
+
+    //trimmed pieces of text between separators, with their spans in input
+    let split = |text: &'a str, text_start: usize, separator: fn(char) -> bool| {
+        let mut pieces: Vec<Argument<'a>> = Vec::new();
+        let mut position = text_start;
+        for piece in text.split(separator) {
+            let piece_start = position + piece.len() - piece.trim_start().len();
+            pieces.push((piece.trim(), piece_start..piece_start + piece.trim().len()));
+            position += piece.len() + 1;
+        }
+        pieces
+    };
+
+    let mut components = match inner.contains(',') {
+        true if legacy => split(inner, inner_start, |c| c == ','),
+        true => return Err(invalid()),
+        false => match inner.split_once('/') {
+            Some((before, alpha)) => {
+                let mut components = split(before, inner_start, char::is_whitespace);
+                components.retain(|(component, _)| !component.is_empty());
+                if components.len() != 3 {
+                    return Err(invalid());
+                }
+                components.extend(split(alpha, inner_start + before.len() + 1, |_| false));
+                components
+            }
+            None => {
+                let mut components = split(inner, inner_start, char::is_whitespace);
+                components.retain(|(component, _)| !component.is_empty());
+                if components.len() != 3 {
+                    return Err(invalid());
+                }
+                components
+            }
+        },
+    };
+    if components.iter().any(|(component, _)| component.is_empty()) {
+        return Err(invalid());
+    }
+    match components.len() {
+        3 => Ok((components, None)),
+        4 => {
+            let alpha = components.pop();
+            Ok((components, alpha))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+//a number, or with a percent sign the given share of percent_of, none counts as 0
+fn css_value(value: &str, percent_of: f64) -> Option<f64> {
+    if value.eq_ignore_ascii_case("none") {
+        return Some(0.0);
+    }
+    match value.strip_suffix('%') {
+        Some(percent) => percent
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|val| val * percent_of / 100.0),
+        None => value.parse::<f64>().ok(),
+    }
+}
+
+//a hue in degrees, or with a deg, grad, rad or turn unit, none counts as 0
+fn css_hue(hue: &str) -> Option<f64> {
+    let hue = hue.to_ascii_lowercase();
+    let (value, scale) = if let Some(value) = hue.strip_suffix("deg") {
+        (value, 1.0)
+    } else if let Some(value) = hue.strip_suffix("grad") {
+        (value, 0.9)
+    } else if let Some(value) = hue.strip_suffix("rad") {
+        (value, 180.0 / std::f64::consts::PI)
+    } else if let Some(value) = hue.strip_suffix("turn") {
+        (value, 360.0)
+    } else {
+        (hue.as_str(), 1.0)
+    };
+    match value.trim() {
+        "none" => Some(0.0),
+        value => value.parse::<f64>().ok().map(|val| val * scale),
+    }
+}
+
+///how translucent colors are blended over what is below them
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Compositing {
+    //blends the gamma encoded channels, which darkens translucent overlays
+    #[default]
+    Srgb,
+    //blends in linear light and encodes the result again
+    Linear,
+}
+
+///how an overlay's channels combine with the color below it before alpha is applied,
+///as in CSS mix-blend-mode
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Add,
+    Subtract,
+    Difference,
+    Darken,
+    Lighten,
+    //the overlay's hue with the saturation and luminosity below
+    Hue,
+    Saturation,
+    Luminosity,
+}
+
+///which way around the hue circle polar spaces interpolate, as in CSS Color 4
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum HueInterpolation {
+    Shorter,
+    Longer,
+    Increasing,
+    Decreasing,
+}
+
+///the space Color::mix interpolates in
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ColorSpace {
+    Srgb,
+    LinearSrgb,
+    Hsl(HueInterpolation),
+    Hsv(HueInterpolation),
+    Lab,
+    Lch(HueInterpolation),
+    Oklab,
+    Oklch(HueInterpolation),
+}
+
+impl ColorSpace {
+    //index of the hue component, how to interpolate it and the chroma below which it means nothing
+    fn hue(&self) -> Option<(usize, HueInterpolation, f64)> {
+        match *self {
+            ColorSpace::Hsl(interpolation) | ColorSpace::Hsv(interpolation) => {
+                Some((0, interpolation, 1e-6))
+            }
+            ColorSpace::Lch(interpolation) => Some((2, interpolation, 0.02)),
+            ColorSpace::Oklch(interpolation) => Some((2, interpolation, 2e-4)),
+            _ => None,
+        }
+    }
+}
+
+//channels from 0 to 1 of the backdrop and source mixed by a blend mode, following the W3C compositing spec
+fn blend(blend_mode: BlendMode, backdrop: [f64; 3], source: [f64; 3]) -> [f64; 3] {
+    let separable =
+        |function: fn(f64, f64) -> f64| [0, 1, 2].map(|i| function(backdrop[i], source[i]));
+    match blend_mode {
+        BlendMode::Normal => source,
+        BlendMode::Multiply => separable(|b, s| b * s),
+        BlendMode::Screen => separable(|b, s| b + s - b * s),
+        BlendMode::Overlay => separable(|b, s| match b <= 0.5 {
+            true => s * 2.0 * b,
+            false => s + (2.0 * b - 1.0) - s * (2.0 * b - 1.0),
+        }),
+        BlendMode::Add => separable(|b, s| (b + s).min(1.0)),
+        BlendMode::Subtract => separable(|b, s| (b - s).max(0.0)),
+        BlendMode::Difference => separable(|b, s| (b - s).abs()),
+        BlendMode::Darken => separable(f64::min),
+        BlendMode::Lighten => separable(f64::max),
+        BlendMode::Hue => set_lum(set_sat(source, sat(backdrop)), lum(backdrop)),
+        BlendMode::Saturation => set_lum(set_sat(backdrop, sat(source)), lum(backdrop)),
+        BlendMode::Luminosity => set_lum(backdrop, lum(source)),
+    }
+}
+
+fn lum([red, green, blue]: [f64; 3]) -> f64 {
+    0.3 * red + 0.59 * green + 0.11 * blue
+}
+
+fn sat(color: [f64; 3]) -> f64 {
+    color.iter().cloned().fold(f64::MIN, f64::max) - color.iter().cloned().fold(f64::MAX, f64::min)
+}
+
+fn set_lum(color: [f64; 3], luminosity: f64) -> [f64; 3] {
+    let shift = luminosity - lum(color);
+    let color = color.map(|channel| channel + shift);
+    let luminosity = lum(color);
+    let min = color.iter().cloned().fold(f64::MAX, f64::min);
+    let max = color.iter().cloned().fold(f64::MIN, f64::max);
+    color.map(|channel| {
+        if min < 0.0 {
+            luminosity + (channel - luminosity) * luminosity / (luminosity - min)
+        } else if max > 1.0 {
+            luminosity + (channel - luminosity) * (1.0 - luminosity) / (max - luminosity)
+        } else {
+            channel
+        }
+    })
+}
+
+fn set_sat(color: [f64; 3], saturation: f64) -> [f64; 3] {
+    let mut order = [0, 1, 2];
+    order.sort_by(|&a, &b| color[a].total_cmp(&color[b]));
+    let [min, mid, max] = order;
+    let mut result = [0.0; 3];
+    if color[max] > color[min] {
+        result[mid] = (color[mid] - color[min]) * saturation / (color[max] - color[min]);
+        result[max] = saturation;
+    }
+    result
+}
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub struct Color {
@@ -13,29 +353,20 @@ pub struct Color {
 
 impl Display for Color {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let color_name: &str = self.get_name();
-        match (!color_name.is_empty(), self.alpha == 255) {
-            (true, true) => write!(
-                f,
-                "({} - red:{}, green:{}, blue:{})",
-                color_name, self.red, self.green, self.blue
-            ),
-            (true, false) => write!(
-                f,
-                "({} - red:{}, green:{}, blue:{}, alpha:{})",
-                color_name, self.red, self.green, self.blue, self.alpha
-            ),
-            (false, true) => write!(
-                f,
-                "(red:{}, green:{}, blue:{})",
-                self.red, self.green, self.blue
-            ),
-            (false, false) => write!(
-                f,
-                "(red:{}, green:{}, blue:{}, alpha:{})",
-                self.red, self.green, self.blue, self.alpha
-            ),
-        }
+        write!(f, "{}", self.to_css())
+    }
+}
+
+//rounds to a few decimals without trailing zeros, enough to land on the same u8 channels again
+fn css_number(val: f64, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, val);
+    let text = match text.contains('.') {
+        true => text.trim_end_matches('0').trim_end_matches('.'),
+        false => &text,
+    };
+    match text {
+        "-0" => "0".to_string(),
+        text => text.to_string(),
     }
 }
 
@@ -130,6 +461,207 @@ impl Color {
         Color::from_hsla(hue, saturation, luminosity, 1.0)
     }
 
+    ///whiteness and blackness from 0 to 1, together they are scaled down to at most 1
+    pub fn from_hwba(hue: f64, whiteness: f64, blackness: f64, alpha: f64) -> Color {
+        let whiteness = whiteness.clamp(0.0, 1.0);
+        let blackness = blackness.clamp(0.0, 1.0);
+        let total = (whiteness + blackness).max(1.0);
+        let (whiteness, blackness) = (whiteness / total, blackness / total);
+        let pure = [0.0, 240.0, 120.0].map(|offset| {
+            let channel = ((3.0 - (hue.rem_euclid(360.0) + offset) % 360.0 / 60.0).abs() - 1.0)
+                .clamp(0.0, 1.0);
+            ((channel * (1.0 - whiteness - blackness) + whiteness) * 255.0).round() as u8
+        });
+        Color::from_rgba(pure[0], pure[1], pure[2], alpha)
+    }
+
+    pub fn from_hwb(hue: f64, whiteness: f64, blackness: f64) -> Color {
+        Color::from_hwba(hue, whiteness, blackness, 1.0)
+    }
+
+    ///CIELAB with lightness from 0 to 100 under a D50 white, colors outside sRGB are clipped
+    pub fn from_laba(lightness: f64, a: f64, b: f64, alpha: f64) -> Color {
+        let [red, green, blue] = linear_srgb_to_channels(lab_to_linear_srgb([lightness, a, b]));
+        Color::from_rgba(red, green, blue, alpha)
+    }
+
+    pub fn from_lab(lightness: f64, a: f64, b: f64) -> Color {
+        Color::from_laba(lightness, a, b, 1.0)
+    }
+
+    ///polar CIELAB, hue in degrees
+    pub fn from_lcha(lightness: f64, chroma: f64, hue: f64, alpha: f64) -> Color {
+        let [lightness, a, b] = polar_to_rectangular([lightness, chroma, hue]);
+        Color::from_laba(lightness, a, b, alpha)
+    }
+
+    pub fn from_lch(lightness: f64, chroma: f64, hue: f64) -> Color {
+        Color::from_lcha(lightness, chroma, hue, 1.0)
+    }
+
+    ///OKLab with lightness from 0 to 1, colors outside sRGB are clipped
+    pub fn from_oklaba(lightness: f64, a: f64, b: f64, alpha: f64) -> Color {
+        let [red, green, blue] = linear_srgb_to_channels(oklab_to_linear_srgb([lightness, a, b]));
+        Color::from_rgba(red, green, blue, alpha)
+    }
+
+    pub fn from_oklab(lightness: f64, a: f64, b: f64) -> Color {
+        Color::from_oklaba(lightness, a, b, 1.0)
+    }
+
+    ///polar OKLab, hue in degrees
+    pub fn from_oklcha(lightness: f64, chroma: f64, hue: f64, alpha: f64) -> Color {
+        let [lightness, a, b] = polar_to_rectangular([lightness, chroma, hue]);
+        Color::from_oklaba(lightness, a, b, alpha)
+    }
+
+    pub fn from_oklch(lightness: f64, chroma: f64, hue: f64) -> Color {
+        Color::from_oklcha(lightness, chroma, hue, 1.0)
+    }
+
+    ///saturation and value from 0 to 1
+    pub fn from_hsva(hue: f64, saturation: f64, value: f64, alpha: f64) -> Color {
+        let saturation = saturation.clamp(0.0, 1.0);
+        let value = value.clamp(0.0, 1.0);
+        let luminosity = value * (1.0 - saturation / 2.0);
+        let saturation = match luminosity.min(1.0 - luminosity) {
+            0.0 => 0.0,
+            denominator => (value - luminosity) / denominator,
+        };
+        Color::from_hsla(hue, saturation, luminosity, alpha)
+    }
+
+    pub fn from_hsv(hue: f64, saturation: f64, value: f64) -> Color {
+        Color::from_hsva(hue, saturation, value, 1.0)
+    }
+
+    ///hue in degrees from 0 to 360, saturation and value from 0 to 1
+    pub fn to_hsv(&self) -> (f64, f64, f64) {
+        let max = self.red.max(self.green).max(self.blue) as f64 / 255.0;
+        let min = self.red.min(self.green).min(self.blue) as f64 / 255.0;
+        let saturation = match max {
+            0.0 => 0.0,
+            max => (max - min) / max,
+        };
+        (self.hue().rem_euclid(360.0), saturation, max)
+    }
+
+    ///CIELAB under a D50 white, lightness from 0 to 100
+    pub fn to_lab(&self) -> (f64, f64, f64) {
+        let [lightness, a, b] = linear_srgb_to_lab(self.linear_channels());
+        (lightness, a, b)
+    }
+
+    ///polar CIELAB, hue in degrees from 0 to 360
+    pub fn to_lch(&self) -> (f64, f64, f64) {
+        let [lightness, chroma, hue] =
+            rectangular_to_polar(linear_srgb_to_lab(self.linear_channels()));
+        (lightness, chroma, hue)
+    }
+
+    ///OKLab, lightness from 0 to 1
+    pub fn to_oklab(&self) -> (f64, f64, f64) {
+        let [lightness, a, b] = linear_srgb_to_oklab(self.linear_channels());
+        (lightness, a, b)
+    }
+
+    ///polar OKLab, hue in degrees from 0 to 360
+    pub fn to_oklch(&self) -> (f64, f64, f64) {
+        let [lightness, chroma, hue] =
+            rectangular_to_polar(linear_srgb_to_oklab(self.linear_channels()));
+        (lightness, chroma, hue)
+    }
+
+    fn linear_channels(&self) -> [f64; 3] {
+        channels_to_linear_srgb([self.red, self.green, self.blue])
+    }
+
+    fn components(&self, space: ColorSpace) -> [f64; 3] {
+        let tuple = |(x, y, z): (f64, f64, f64)| [x, y, z];
+        match space {
+            ColorSpace::Srgb => {
+                [self.red, self.green, self.blue].map(|channel| channel as f64 / 255.0)
+            }
+            ColorSpace::LinearSrgb => self.linear_channels(),
+            ColorSpace::Hsl(_) => [
+                self.hue().rem_euclid(360.0),
+                self.saturation(),
+                self.luminosity(),
+            ],
+            ColorSpace::Hsv(_) => tuple(self.to_hsv()),
+            ColorSpace::Lab => tuple(self.to_lab()),
+            ColorSpace::Lch(_) => tuple(self.to_lch()),
+            ColorSpace::Oklab => tuple(self.to_oklab()),
+            ColorSpace::Oklch(_) => tuple(self.to_oklch()),
+        }
+    }
+
+    fn from_components(space: ColorSpace, [x, y, z]: [f64; 3], alpha: f64) -> Color {
+        match space {
+            ColorSpace::Srgb => {
+                let [red, green, blue] =
+                    [x, y, z].map(|channel| (channel * 255.0).round().clamp(0.0, 255.0) as u8);
+                Color::from_rgba(red, green, blue, alpha)
+            }
+            ColorSpace::LinearSrgb => {
+                let [red, green, blue] = linear_srgb_to_channels([x, y, z]);
+                Color::from_rgba(red, green, blue, alpha)
+            }
+            ColorSpace::Hsl(_) => Color::from_hsla(x, y, z, alpha),
+            ColorSpace::Hsv(_) => Color::from_hsva(x, y, z, alpha),
+            ColorSpace::Lab => Color::from_laba(x, y, z, alpha),
+            ColorSpace::Lch(_) => Color::from_lcha(x, y, z, alpha),
+            ColorSpace::Oklab => Color::from_oklaba(x, y, z, alpha),
+            ColorSpace::Oklch(_) => Color::from_oklcha(x, y, z, alpha),
+        }
+    }
+
+    ///interpolates towards other, t from 0 (self) to 1 (other), with premultiplied alpha like CSS color-mix(),
+    ///a gray has no hue in polar spaces and takes the other color's
+    pub fn mix(&self, other: Color, t: f64, space: ColorSpace) -> Color {
+        match (self.valid, other.valid) {
+            (false, _) => return other,
+            (_, false) => return *self,
+            _ => {}
+        }
+        let t = t.clamp(0.0, 1.0);
+        let mut from = self.components(space);
+        let mut to = other.components(space);
+        let hue = space.hue();
+        if let Some((index, interpolation, powerless)) = hue {
+            if from[1] < powerless {
+                from[index] = to[index];
+            } else if to[1] < powerless {
+                to[index] = from[index];
+            }
+            let difference = to[index] - from[index];
+            match interpolation {
+                HueInterpolation::Shorter if difference > 180.0 => from[index] += 360.0,
+                HueInterpolation::Shorter if difference < -180.0 => to[index] += 360.0,
+                HueInterpolation::Longer if 0.0 < difference && difference < 180.0 => {
+                    from[index] += 360.0
+                }
+                HueInterpolation::Longer if -180.0 < difference && difference <= 0.0 => {
+                    to[index] += 360.0
+                }
+                HueInterpolation::Increasing if difference < 0.0 => to[index] += 360.0,
+                HueInterpolation::Decreasing if difference > 0.0 => from[index] += 360.0,
+                _ => {}
+            }
+        }
+
+        let lerp = |from: f64, to: f64| from + (to - from) * t;
+        let from_alpha = self.alpha as f64 / 255.0;
+        let to_alpha = other.alpha as f64 / 255.0;
+        let alpha = lerp(from_alpha, to_alpha);
+        let mixed = [0, 1, 2].map(|i| match hue {
+            Some((index, ..)) if index == i => lerp(from[i], to[i]).rem_euclid(360.0),
+            _ if alpha == 0.0 => lerp(from[i], to[i]),
+            _ => lerp(from[i] * from_alpha, to[i] * to_alpha) / alpha,
+        });
+        Color::from_components(space, mixed, alpha)
+    }
+
     pub fn hue(&self) -> f64 {
         let red: f64 = self.red as f64 / 255.0;
         let green: f64 = self.green as f64 / 255.0;
@@ -204,8 +736,9 @@ impl Color {
 
     pub fn with_overlay(self, overlay: Color) -> Color {
         match (self, overlay) {
-            (Colors::INVALID, value) => value,
-            (value, Colors::INVALID) => value,
+            //invalid colors and an unresolved current color leave the other one as it is
+            (under, over) if !under.valid => over,
+            (under, over) if !over.valid => under,
             (under, over) => Color {
                 red: ((over.red as f64 * over.alpha as f64
                     + under.red as f64 * (255 - over.alpha) as f64)
@@ -226,6 +759,68 @@ impl Color {
         }
     }
 
+    ///with_overlay, blending in the given compositing mode
+    pub fn with_overlay_in(self, overlay: Color, compositing: Compositing) -> Color {
+        if compositing == Compositing::Srgb || !self.valid || !overlay.valid {
+            return self.with_overlay(overlay);
+        }
+        let (under, over) = (self, overlay);
+        let alpha =
+            (over.alpha as f64 + under.alpha as f64 * (255 - over.alpha) as f64 / 255.0) as u8;
+        match over.alpha {
+            0 => return Color { alpha, ..under },
+            255 => return Color { alpha, ..over },
+            _ => {}
+        }
+        let tables = linear_tables();
+        let over_alpha = over.alpha as f32 / 255.0;
+        let blend = |under: u8, over: u8| {
+            tables.to_srgb(
+                tables.to_linear[over as usize] * over_alpha
+                    + tables.to_linear[under as usize] * (1.0 - over_alpha),
+            )
+        };
+        Color {
+            red: blend(under.red, over.red),
+            green: blend(under.green, over.green),
+            blue: blend(under.blue, over.blue),
+            alpha,
+            valid: true,
+        }
+    }
+
+    ///with_overlay_in, with the overlay's channels first mixed with the ones below by the blend mode,
+    ///a translucent color below shows more of the plain overlay
+    pub fn with_blend(
+        self,
+        overlay: Color,
+        blend_mode: BlendMode,
+        compositing: Compositing,
+    ) -> Color {
+        if blend_mode == BlendMode::Normal || !self.valid || !overlay.valid {
+            return self.with_overlay_in(overlay, compositing);
+        }
+        let channels =
+            |color: Color| [color.red, color.green, color.blue].map(|c| c as f64 / 255.0);
+        let (backdrop, source) = (channels(self), channels(overlay));
+        let blended = blend(blend_mode, backdrop, source);
+        let backdrop_alpha = self.alpha as f64 / 255.0;
+        let [red, green, blue] = [0, 1, 2].map(|i| {
+            (((1.0 - backdrop_alpha) * source[i] + backdrop_alpha * blended[i]) * 255.0)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        });
+        self.with_overlay_in(
+            Color {
+                red,
+                green,
+                blue,
+                ..overlay
+            },
+            compositing,
+        )
+    }
+
     pub fn inverted(&self) -> Color {
         Color {
             red: 255 - self.red,
@@ -236,10 +831,62 @@ impl Color {
         }
     }
 
+    ///#rrggbbaa, which from_html parses back into an equal color
+    pub fn to_css(&self) -> String {
+        if !self.valid {
+            return self.invalid_css();
+        }
+        format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            self.red, self.green, self.blue, self.alpha
+        )
+    }
+
+    fn invalid_css(&self) -> String {
+        match *self {
+            Colors::CURRENT_COLOR => "currentcolor".to_string(),
+            _ => "invalid".to_string(),
+        }
+    }
+
+    ///rgba(r, g, b, a) with a from 0 to 1, which from_html parses back into an equal color
+    pub fn to_css_rgba(&self) -> String {
+        if !self.valid {
+            return self.invalid_css();
+        }
+        format!(
+            "rgba({}, {}, {}, {})",
+            self.red,
+            self.green,
+            self.blue,
+            css_number(self.alpha as f64 / 255.0, 3)
+        )
+    }
+
+    ///hsl(h, s%, l%), or hsla when not opaque, which from_html parses back into an equal color
+    pub fn to_css_hsl(&self) -> String {
+        if !self.valid {
+            return self.invalid_css();
+        }
+        let hsl = format!(
+            "{}, {}%, {}%",
+            css_number(self.hue().rem_euclid(360.0), 2),
+            css_number(self.saturation() * 100.0, 2),
+            css_number(self.luminosity() * 100.0, 2)
+        );
+        match self.alpha {
+            255 => format!("hsl({})", hsl),
+            alpha => format!("hsla({}, {})", hsl, css_number(alpha as f64 / 255.0, 3)),
+        }
+    }
+
     // here begin the definitions, feel free to fold them
 
     #[allow(unreachable_patterns)]
     pub fn get_name(&self) -> &str {
+        if *self == Colors::CURRENT_COLOR {
+            return "CURRENT_COLOR";
+        }
         if !self.valid {
             return "INVALID";
         }
@@ -408,8 +1055,10 @@ impl Color {
     }
 
     /* spellchecker: disable */
-    pub fn from_name(name: &str) -> Result<Color, &str> {
+    pub fn from_name(name: &str) -> Result<Color, ColorParseError> {
         match name.to_lowercase().replace("_", "").trim() {
+            "invalid" => Ok(Colors::INVALID),
+            "currentcolor" => Ok(Colors::CURRENT_COLOR),
             "transparent" => Ok(Colors::TRANSPARENT),
             "aliceblue" => Ok(Colors::ALICE_BLUE),
             "antiquewhite" => Ok(Colors::ANTIQUE_WHITE),
@@ -559,148 +1208,136 @@ impl Color {
             "whitesmoke" => Ok(Colors::WHITE_SMOKE),
             "yellowgreen" => Ok(Colors::YELLOW_GREEN),
             "yellow" => Ok(Colors::YELLOW),
-            _ => Err("Couldn't parse color"),
+            _ => {
+                let start = name.len() - name.trim_start().len();
+                Err(ColorParseError::UnknownName {
+                    input: name.to_string(),
+                    span: start..start + name.trim().len(),
+                    expected: "an HTML color name",
+                })
+            }
         }
     }
     /* spellchecker: enable */
 
-    pub fn from_html(mut html: &str) -> Result<Color, &str> {
-        html = html.trim();
+    pub fn from_html(input: &str) -> Result<Color, ColorParseError> {
+        let html = input.trim();
+        let offset = input.len() - input.trim_start().len();
 
-        let from_name = Color::from_name(html);
-        if from_name.is_ok() {
-            return Ok(from_name.unwrap());
-        };
+        if let Ok(color) = Color::from_name(html) {
+            return Ok(color);
+        }
 
-        if html.starts_with('#') {
-            html = &html[1..].trim();
-            let length = html.len();
-            return match length {
-                8 => match u32::from_str_radix(html, 16) {
+        if let Some(hex) = html.strip_prefix('#') {
+            let start = offset + 1 + hex.len() - hex.trim_start().len();
+            let hex = hex.trim();
+            let invalid = |expected| ColorParseError::InvalidHex {
+                input: input.to_string(),
+                span: start..start + hex.len(),
+                expected,
+            };
+            return match hex.len() {
+                8 => match u32::from_str_radix(hex, 16) {
                     Ok(val) => Ok(Color::from_u32(val)),
-                    Err(_) => Err("Couldn't parse #RRGGBBAA format"),
+                    Err(_) => Err(invalid("#RRGGBBAA")),
                 },
-                6 => match u32::from_str_radix(html, 16) {
+                6 => match u32::from_str_radix(hex, 16) {
                     Ok(val) => Ok(Color::from_u32(val * 0x100 + 0xff)),
-                    Err(_) => Err("Couldn't parse #RRGGBB format"),
+                    Err(_) => Err(invalid("#RRGGBB")),
                 },
-                4 => match u16::from_str_radix(html, 16) {
+                4 => match u16::from_str_radix(hex, 16) {
                     Ok(val) => Ok(Color::from_u16(val)),
-                    Err(_) => Err("Couldn't parse #RGBA format"),
+                    Err(_) => Err(invalid("#RGBA")),
                 },
-                3 => match u16::from_str_radix(html, 16) {
+                3 => match u16::from_str_radix(hex, 16) {
                     Ok(val) => Ok(Color::from_u16(val * 0x10 + 0xf)),
-                    Err(_) => Err("Couldn't parse #RGB format"),
+                    Err(_) => Err(invalid("#RGB")),
                 },
-                _ => Err("Invalid color format. Supported hexadecimal formats are #RRGGBBAA, #RRGGBB, #RGBA and #RGB."),
-            };
-        }
-
-        if html.starts_with("rgba") {
-            html = &html[4..].trim();
-            let rgba: Vec<&str>;
-
-            if html.starts_with('(') && html.ends_with(')') {
-                rgba = html[1..html.len() - 1].trim().split(',').collect();
-            } else {
-                return Err("Invalid color format. The correct rgba(u8, u8, u8, f64) function structure is rgba(r, g, b, a). Spaces don't matter.");
-            }
-
-            if rgba.len() != 4 {
-                return Err("Invalid color format. The correct rgba(u8, u8, u8, f64) function structure is rgba(r, g, b, a). Spaces don't matter.");
-            }
-
-            return match (
-                rgba[0].trim().parse::<u8>(),
-                rgba[1].trim().parse::<u8>(),
-                rgba[2].trim().parse::<u8>(),
-                process_percent(rgba[3])
-            ) {
-                (Ok(red),Ok(green),Ok(blue),Ok(alpha))=>
-                    Ok(Color::from_rgba(red, green, blue, alpha)),
-
-                _=>Err("Invalid color format. The correct rgba(u8, u8, u8, f64) function structure is rgba(r, g, b, a). Spaces don't matter")
-            };
-        }
-
-        if html.starts_with("rgb") {
-            html = &html[3..].trim();
-            let rgba: Vec<&str>;
-
-            if html.starts_with('(') && html.ends_with(')') {
-                rgba = html[1..html.len() - 1].trim().split(',').collect();
-            } else {
-                return Err("Invalid color format. The correct rgb(u8, u8, u8) function structure is rgb(r, g, b). Spaces don't matter.");
-            }
-
-            if rgba.len() != 3 {
-                return Err("Invalid color format. The correct rgb(u8, u8, u8) function structure is rgb(r, g, b). Spaces don't matter.");
-            }
-
-            return match (
-                rgba[0].trim().parse::<u8>(),
-                rgba[1].trim().parse::<u8>(),
-                rgba[2].trim().parse::<u8>()
-            ) {
-                (Ok(red),Ok(green),Ok(blue))=>
-                    Ok(Color::from_rgb(red, green, blue)),
-
-                _=>Err("Invalid color format. The correct rgb(u8, u8, u8) function structure is rgb(r, g, b). Spaces don't matter")
+                _ => Err(invalid("#RRGGBBAA, #RRGGBB, #RGBA or #RGB")),
             };
         }
 
-        if html.starts_with("hsla") {
-            html = &html[4..].trim();
-            let rgba: Vec<&str>;
-
-            if html.starts_with('(') && html.ends_with(')') {
-                rgba = html[1..html.len() - 1].trim().split(',').collect();
-            } else {
-                return Err("Invalid color format. The correct hsla(f64, f64, f64, f64) function structure is hsla(h, s, l, a). Spaces don't matter.");
-            }
-
-            if rgba.len() != 4 {
-                return Err("Invalid color format. The correct hsla(f64, f64, f64, f64) function structure is hsla(h, s, l, a). Spaces don't matter.");
-            }
-
-            return match (
-                rgba[0].trim().parse::<f64>(),
-                process_percent(rgba[1]),
-                process_percent(rgba[2]),
-                process_percent(rgba[3])
-            ) {
-                (Ok(hue),Ok(saturation),Ok(luminosity),Ok(alpha))=>
-                    Ok(Color::from_hsla(hue, saturation, luminosity, alpha)),
-
-                _=>Err("Invalid color format. The correct hsla(f64, f64, f64, f64) function structure is hsla(h, s, l, a). Spaces don't matter.")
-            };
-        }
-
-        if html.starts_with("hsl") {
-            html = &html[3..].trim();
-            let rgba: Vec<&str>;
-
-            if html.starts_with('(') && html.ends_with(')') {
-                rgba = html[1..html.len() - 1].trim().split(',').collect();
-            } else {
-                return Err("Invalid color format. The correct hsl(f64, f64, f64) function structure is hsl(h, s, l). Spaces don't matter.");
-            }
+        let unknown_format = || ColorParseError::UnknownFormat {
+            input: input.to_string(),
+            span: offset..offset + html.len(),
+            expected: COLOR_FORMATS,
+        };
+        let (name, arguments) = match html.split_once('(') {
+            Some((name, _)) => (name.trim().to_ascii_lowercase(), &html[name.len()..]),
+            None => return Err(unknown_format()),
+        };
+        let (legacy, expected) = match name.as_str() {
+            "rgb" | "rgba" => (true, "rgb(r g b [/ a]) or rgb(r, g, b[, a])"),
+            "hsl" | "hsla" => (true, "hsl(h s l [/ a]) or hsl(h, s, l[, a])"),
+            "hwb" => (false, "hwb(h w b [/ a])"),
+            "lab" => (false, "lab(l a b [/ a])"),
+            "lch" => (false, "lch(l c h [/ a])"),
+            "oklab" => (false, "oklab(l a b [/ a])"),
+            "oklch" => (false, "oklch(l c h [/ a])"),
+            _ => return Err(unknown_format()),
+        };
+        let start = offset + html.len() - arguments.len();
+        let (components, alpha) = color_arguments(input, arguments, start, legacy, expected)?;
 
-            if rgba.len() != 3 {
-                return Err("Invalid color format. The correct hsl(f64, f64, f64) function structure is hsl(h, s, l). Spaces don't matter.");
+        let invalid = |(_, span): &Argument, expected| ColorParseError::InvalidComponent {
+            input: input.to_string(),
+            span: span.clone(),
+            expected,
+        };
+        let value = |index: usize, percent_of: f64| {
+            css_value(components[index].0, percent_of)
+                .ok_or_else(|| invalid(&components[index], "a number or percentage"))
+        };
+        let channel = |index: usize| match value(index, 255.0) {
+            Ok(val) if (0.0..=255.0).contains(&val) => Ok(val.round() as u8),
+            _ => Err(invalid(
+                &components[index],
+                "a number from 0 to 255 or a percentage",
+            )),
+        };
+        let hue = |index: usize| {
+            css_hue(components[index].0)
+                .ok_or_else(|| invalid(&components[index], "a number or angle"))
+        };
+        //fractions from 0 to 1 or percentages, like the legacy hsl arguments
+        let fraction = |argument: &Argument| match argument.0 {
+            "none" => Ok(0.0),
+            value => {
+                process_percent(value).map_err(|_| invalid(argument, "a number or percentage"))
             }
+        };
+        //the space syntax reads bare numbers as percentages like css color 4,
+        //the legacy comma syntax keeps fractions
+        let commas = arguments.contains(',');
+        let percentage = |argument: &Argument| match commas {
+            true => fraction(argument),
+            false => css_value(argument.0, 100.0)
+                .map(|val| val / 100.0)
+                .ok_or_else(|| invalid(argument, "a number or percentage")),
+        };
+        let alpha = match &alpha {
+            Some(alpha) => fraction(alpha)?,
+            None => 1.0,
+        };
 
-            return match (
-                rgba[0].trim().parse::<f64>(),
-                process_percent(rgba[1]),
-                process_percent(rgba[2])
-            ) {
-                (Ok(hue),Ok(saturation),Ok(luminosity))=>
-                    Ok(Color::from_hsl(hue, saturation, luminosity)),
-
-                _=>Err("Invalid color format. The correct hsl(f64, f64, f64) function structure is hsl(h, s, l). Spaces don't matter.")
-            };
-        }
-        Err("Invalid color name or format. Make sure to use supported HTML color names or formats.")
+        Ok(match name.as_str() {
+            "rgb" | "rgba" => Color::from_rgba(channel(0)?, channel(1)?, channel(2)?, alpha),
+            "hsl" | "hsla" => Color::from_hsla(
+                hue(0)?,
+                percentage(&components[1])?,
+                percentage(&components[2])?,
+                alpha,
+            ),
+            "hwb" => Color::from_hwba(
+                hue(0)?,
+                percentage(&components[1])?,
+                percentage(&components[2])?,
+                alpha,
+            ),
+            "lab" => Color::from_laba(value(0, 100.0)?, value(1, 125.0)?, value(2, 125.0)?, alpha),
+            "lch" => Color::from_lcha(value(0, 100.0)?, value(1, 150.0)?, hue(2)?, alpha),
+            "oklab" => Color::from_oklaba(value(0, 1.0)?, value(1, 0.4)?, value(2, 0.4)?, alpha),
+            _ => Color::from_oklcha(value(0, 1.0)?, value(1, 0.4)?, hue(2)?, alpha),
+        })
     }
 }
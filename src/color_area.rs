@@ -1,5 +1,5 @@
 use crate::{
-    color::Color,
+    color::{BlendMode, Color},
     geometry::Dimension,
     renderer_object_style::{AlignmentX, AlignmentY},
 };
@@ -14,6 +14,7 @@ pub enum ColorLayer {
 pub struct ColorArea {
     pub color: Color,
     pub layer: ColorLayer,
+    pub blend_mode: BlendMode,
     pub x: Dimension,
     pub y: Dimension,
     pub width: Dimension,
@@ -28,6 +29,7 @@ impl ColorArea {
         ColorArea {
             color,
             layer,
+            blend_mode: BlendMode::Normal,
             x: Dimension::Auto,
             y: Dimension::Auto,
             width: Dimension::Auto,
@@ -55,4 +57,9 @@ impl ColorArea {
             (external_alignment_x, external_alignment_y);
         self
     }
+
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) -> &mut Self {
+        self.blend_mode = blend_mode;
+        self
+    }
 }
@@ -0,0 +1,151 @@
+use std::sync::OnceLock;
+
+//conversions between gamma encoded sRGB channels and the CSS color spaces,
+//matrices and constants follow CSS Color 4
+
+//D50 reference white used by CIELAB
+const D50_WHITE: [f64; 3] = [0.3457 / 0.3585, 1.0, (1.0 - 0.3457 - 0.3585) / 0.3585];
+
+//XYZ relative to D50 to linear sRGB, Bradford adaptation to D65 folded in
+const XYZ_D50_TO_LINEAR_SRGB: [[f64; 3]; 3] = [
+    [3.1341359569958707, -1.6173863321612538, -0.4906619460083532],
+    [-0.978795502912089, 1.916254567259524, 0.03344273116131949],
+    [0.07195537988411677, -0.2289768264158322, 1.405386058324125],
+];
+
+//linear sRGB to XYZ relative to D50, the inverse of the above
+const LINEAR_SRGB_TO_XYZ_D50: [[f64; 3]; 3] = [
+    [0.436065742824811, 0.3851514688337912, 0.14307845442264197],
+    [0.22249319175623702, 0.7168870538238823, 0.06061979053616537],
+    [
+        0.013923904500943465,
+        0.09708128566574634,
+        0.7140993584005155,
+    ],
+];
+
+const LAB_EPSILON: f64 = 216.0 / 24389.0;
+const LAB_KAPPA: f64 = 24389.0 / 27.0;
+
+fn multiply(matrix: &[[f64; 3]; 3], vector: [f64; 3]) -> [f64; 3] {
+    matrix.map(|row| row[0] * vector[0] + row[1] * vector[1] + row[2] * vector[2])
+}
+
+///gamma encoded channel from 0 to 1 to linear light
+pub(crate) fn srgb_to_linear(channel: f64) -> f64 {
+    if channel.abs() <= 0.04045 {
+        channel / 12.92
+    } else {
+        channel.signum() * ((channel.abs() + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+///linear light channel to gamma encoded from 0 to 1
+pub(crate) fn linear_to_srgb(channel: f64) -> f64 {
+    if channel.abs() <= 0.0031308 {
+        channel * 12.92
+    } else {
+        channel.signum() * (1.055 * channel.abs().powf(1.0 / 2.4) - 0.055)
+    }
+}
+
+///clips linear sRGB into the gamut and encodes it as u8 channels
+pub(crate) fn linear_srgb_to_channels(rgb: [f64; 3]) -> [u8; 3] {
+    rgb.map(|channel| (linear_to_srgb(channel.clamp(0.0, 1.0)) * 255.0).round() as u8)
+}
+
+pub(crate) fn channels_to_linear_srgb(channels: [u8; 3]) -> [f64; 3] {
+    channels.map(|channel| srgb_to_linear(channel as f64 / 255.0))
+}
+
+///polar lightness, chroma and hue in degrees to rectangular lightness, a and b
+pub(crate) fn polar_to_rectangular([lightness, chroma, hue]: [f64; 3]) -> [f64; 3] {
+    let hue = hue.to_radians();
+    [lightness, chroma * hue.cos(), chroma * hue.sin()]
+}
+
+///rectangular lightness, a and b to lightness, chroma and hue in degrees from 0 to 360
+pub(crate) fn rectangular_to_polar([lightness, a, b]: [f64; 3]) -> [f64; 3] {
+    [
+        lightness,
+        a.hypot(b),
+        b.atan2(a).to_degrees().rem_euclid(360.0),
+    ]
+}
+
+pub(crate) fn linear_srgb_to_lab(rgb: [f64; 3]) -> [f64; 3] {
+    let xyz = multiply(&LINEAR_SRGB_TO_XYZ_D50, rgb);
+    let [fx, fy, fz] = [0, 1, 2].map(|i| match xyz[i] / D50_WHITE[i] {
+        ratio if ratio > LAB_EPSILON => ratio.cbrt(),
+        ratio => (LAB_KAPPA * ratio + 16.0) / 116.0,
+    });
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+pub(crate) fn lab_to_linear_srgb([lightness, a, b]: [f64; 3]) -> [f64; 3] {
+    let fy = (lightness + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+    let inverse = |f: f64| match f.powi(3) {
+        cube if cube > LAB_EPSILON => cube,
+        _ => (116.0 * f - 16.0) / LAB_KAPPA,
+    };
+    let y = match lightness > LAB_KAPPA * LAB_EPSILON {
+        true => fy.powi(3),
+        false => lightness / LAB_KAPPA,
+    };
+    let xyz = [
+        inverse(fx) * D50_WHITE[0],
+        y * D50_WHITE[1],
+        inverse(fz) * D50_WHITE[2],
+    ];
+    multiply(&XYZ_D50_TO_LINEAR_SRGB, xyz)
+}
+
+pub(crate) fn oklab_to_linear_srgb([lightness, a, b]: [f64; 3]) -> [f64; 3] {
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    [
+        4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s,
+        -1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s,
+        -0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s,
+    ]
+}
+
+pub(crate) fn linear_srgb_to_oklab([red, green, blue]: [f64; 3]) -> [f64; 3] {
+    let l = (0.4122214708 * red + 0.5363325363 * green + 0.0514459929 * blue).cbrt();
+    let m = (0.2119034982 * red + 0.6806995451 * green + 0.1073969566 * blue).cbrt();
+    let s = (0.0883024619 * red + 0.2817188376 * green + 0.6299787005 * blue).cbrt();
+    [
+        0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s,
+        1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s,
+        0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s,
+    ]
+}
+
+//resolution of the linear light to sRGB table, fine enough to tell the darkest channels apart
+const LINEAR_STEPS: usize = 1 << 14;
+
+pub(crate) struct LinearTables {
+    pub(crate) to_linear: [f32; 256],
+    to_srgb: Vec<u8>,
+}
+
+impl LinearTables {
+    ///nearest u8 channel for a linear light value from 0 to 1
+    pub(crate) fn to_srgb(&self, linear: f32) -> u8 {
+        self.to_srgb[(linear.clamp(0.0, 1.0) * LINEAR_STEPS as f32 + 0.5) as usize]
+    }
+}
+
+///lookup tables for linear light compositing, built on first use
+pub(crate) fn linear_tables() -> &'static LinearTables {
+    static TABLES: OnceLock<LinearTables> = OnceLock::new();
+    TABLES.get_or_init(|| LinearTables {
+        to_linear: std::array::from_fn(|channel| srgb_to_linear(channel as f64 / 255.0) as f32),
+        to_srgb: (0..=LINEAR_STEPS)
+            .map(|step| (linear_to_srgb(step as f64 / LINEAR_STEPS as f64) * 255.0).round() as u8)
+            .collect(),
+    })
+}
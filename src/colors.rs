@@ -11,6 +11,15 @@ impl Colors {
         valid: false,
     };
 
+    //the foreground of the cell, resolved when a color area is drawn
+    pub const CURRENT_COLOR: Color = Color {
+        red: 0,
+        green: 0,
+        blue: 0,
+        alpha: 255,
+        valid: false,
+    };
+
     pub const TRANSPARENT: Color = Color {
         red: 0,
         green: 0,
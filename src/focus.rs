@@ -0,0 +1,146 @@
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    input::{InputEvent, Key, KeyEvent, KeyEventKind},
+    renderer_object_value::RendererObjectValue,
+    renderer_object_wrapper::RendererObject,
+};
+
+pub type KeyHandler = Arc<dyn Fn(&mut RendererObject, &KeyEvent) -> bool + Send + Sync>;
+
+pub struct FocusManager {
+    root: RendererObject,
+    focused: Option<RendererObject>,
+}
+
+impl FocusManager {
+    pub fn new(root: &RendererObject) -> FocusManager {
+        FocusManager {
+            root: root.clone(),
+            focused: None,
+        }
+    }
+
+    pub fn get_focused(&self) -> Option<RendererObject> {
+        self.focused.clone()
+    }
+
+    pub fn focus(&mut self, object: Option<&RendererObject>) -> &mut Self {
+        if let Some(previous) = &mut self.focused {
+            previous.set_focused(false);
+        }
+        self.focused = object.cloned();
+        if let Some(current) = &mut self.focused {
+            current.set_focused(true);
+        }
+        self
+    }
+
+    pub fn focus_next(&mut self) -> &mut Self {
+        self.move_focus(1)
+    }
+
+    pub fn focus_previous(&mut self) -> &mut Self {
+        self.move_focus(-1)
+    }
+
+    ///focusable objects, explicit tab indices first, then the rest in tree order
+    pub fn tab_order(&self) -> Vec<RendererObject> {
+        let mut objects: Vec<RendererObject> = Vec::new();
+        collect_focusable(&self.root.value, &mut objects);
+        objects.sort_by_key(|object| match object.get_tab_index() {
+            Some(index) => (0, index),
+            None => (1, 0),
+        });
+        objects
+    }
+
+    fn move_focus(&mut self, step: i64) -> &mut Self {
+        let order = self.tab_order();
+        if order.is_empty() {
+            return self.focus(None);
+        }
+        let current = self
+            .focused
+            .as_ref()
+            .and_then(|focused| order.iter().position(|object| object.same_object(focused)));
+        let next = match current {
+            Some(index) => (index as i64 + step).rem_euclid(order.len() as i64) as usize,
+            None if step < 0 => order.len() - 1,
+            None => 0,
+        };
+        self.focus(Some(&order[next]))
+    }
+
+    ///routes key events to the focused object and bubbles them up through its parents,
+    ///unconsumed tab presses move the focus, returns true if the event was consumed
+    pub fn handle_event(&mut self, event: &InputEvent) -> bool {
+        let key_event = match event {
+            InputEvent::Key(key_event) => key_event,
+            _ => return false,
+        };
+
+        if let Some(focused) = &self.focused {
+            let mut path = Vec::new();
+            if find_path(&self.root.value, &focused.value, &mut path) {
+                for object in path.iter_mut().rev() {
+                    let handler = object.new_value.read().unwrap().key_handler.clone();
+                    if let Some(handler) = handler {
+                        if handler(object, key_event) {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+
+        if key_event.kind == KeyEventKind::Release {
+            return false;
+        }
+        match key_event.key {
+            Key::Tab if !key_event.modifiers.shift => {
+                self.focus_next();
+                true
+            }
+            Key::Tab | Key::BackTab => {
+                self.focus_previous();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn collect_focusable(value: &Arc<RwLock<RendererObjectValue>>, objects: &mut Vec<RendererObject>) {
+    let object = RendererObject::from_value(value.clone());
+    //hidden objects and everything in them can't take the focus
+    if !object.get_visible() || !object.get_displayed() {
+        return;
+    }
+    if object.get_focusable() {
+        objects.push(object.clone());
+    }
+    for child in &object.new_value.read().unwrap().children {
+        collect_focusable(child, objects);
+    }
+}
+
+//path from the root to the target, both included
+fn find_path(
+    value: &Arc<RwLock<RendererObjectValue>>,
+    target: &Arc<RwLock<RendererObjectValue>>,
+    path: &mut Vec<RendererObject>,
+) -> bool {
+    let object = RendererObject::from_value(value.clone());
+    path.push(object.clone());
+    if Arc::ptr_eq(value, target) {
+        return true;
+    }
+    for child in &object.new_value.read().unwrap().children {
+        if find_path(child, target, path) {
+            return true;
+        }
+    }
+    path.pop();
+    false
+}
@@ -1,3 +1,5 @@
+use std::{error::Error, fmt::Display, ops::Range, sync::Mutex};
+
 pub struct Dimensions;
 
 impl Dimensions {
@@ -154,28 +156,475 @@ pub enum Dimension {
     VH(f64),
     VMin(f64),
     VMax(f64),
+    //calc(), min(), max() and clamp(), built through Dimension::calc so the enum stays Copy
+    Calc(&'static DimensionExpression),
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum DimensionExpression {
+    Value(Dimension),
+    Number(f64),
+    Add(Box<DimensionExpression>, Box<DimensionExpression>),
+    Sub(Box<DimensionExpression>, Box<DimensionExpression>),
+    Mul(Box<DimensionExpression>, Box<DimensionExpression>),
+    Div(Box<DimensionExpression>, Box<DimensionExpression>),
+    Min(Vec<DimensionExpression>),
+    Max(Vec<DimensionExpression>),
+    //minimum, preferred, maximum
+    Clamp(
+        Box<DimensionExpression>,
+        Box<DimensionExpression>,
+        Box<DimensionExpression>,
+    ),
+}
+
+impl Display for Dimension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Dimension::Auto => write!(f, "auto"),
+            Dimension::Pixel(val) => write!(f, "{}px", val),
+            Dimension::Percent(val) => write!(f, "{}%", val),
+            Dimension::PW(val) => write!(f, "{}pw", val),
+            Dimension::PH(val) => write!(f, "{}ph", val),
+            Dimension::PMin(val) => write!(f, "{}pmin", val),
+            Dimension::PMax(val) => write!(f, "{}pmax", val),
+            Dimension::VW(val) => write!(f, "{}vw", val),
+            Dimension::VH(val) => write!(f, "{}vh", val),
+            Dimension::VMin(val) => write!(f, "{}vmin", val),
+            Dimension::VMax(val) => write!(f, "{}vmax", val),
+            Dimension::Calc(expression) => match expression {
+                DimensionExpression::Min(_)
+                | DimensionExpression::Max(_)
+                | DimensionExpression::Clamp(..) => write!(f, "{}", expression),
+                _ => write!(f, "calc({})", expression),
+            },
+        }
+    }
+}
+
+impl Display for DimensionExpression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_css(f, 0)
+    }
+}
+
+impl Dimension {
+    ///css text that from_html parses back into an equal dimension
+    pub fn to_css(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl DimensionExpression {
+    //sums are level 0, products 1 and everything else 2,
+    //operands below the level their position needs get parentheses to keep the tree's shape
+    fn level(&self) -> u8 {
+        match self {
+            DimensionExpression::Add(..) | DimensionExpression::Sub(..) => 0,
+            DimensionExpression::Mul(..) | DimensionExpression::Div(..) => 1,
+            _ => 2,
+        }
+    }
+
+    fn write_css(&self, f: &mut std::fmt::Formatter<'_>, level: u8) -> std::fmt::Result {
+        if self.level() < level {
+            write!(f, "(")?;
+            self.write_css(f, 0)?;
+            return write!(f, ")");
+        }
+        let list = |f: &mut std::fmt::Formatter<'_>, list: &[&DimensionExpression]| {
+            for (i, expression) in list.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                expression.write_css(f, 0)?;
+            }
+            write!(f, ")")
+        };
+        match self {
+            DimensionExpression::Value(dimension) => write!(f, "{}", dimension),
+            DimensionExpression::Number(val) => write!(f, "{}", val),
+            DimensionExpression::Add(a, b) | DimensionExpression::Sub(a, b) => {
+                a.write_css(f, 0)?;
+                match self {
+                    DimensionExpression::Add(..) => write!(f, " + ")?,
+                    _ => write!(f, " - ")?,
+                }
+                b.write_css(f, 1)
+            }
+            DimensionExpression::Mul(a, b) | DimensionExpression::Div(a, b) => {
+                a.write_css(f, 1)?;
+                match self {
+                    DimensionExpression::Mul(..) => write!(f, " * ")?,
+                    _ => write!(f, " / ")?,
+                }
+                b.write_css(f, 2)
+            }
+            DimensionExpression::Min(expressions) => {
+                write!(f, "min(")?;
+                list(f, &expressions.iter().collect::<Vec<_>>())
+            }
+            DimensionExpression::Max(expressions) => {
+                write!(f, "max(")?;
+                list(f, &expressions.iter().collect::<Vec<_>>())
+            }
+            DimensionExpression::Clamp(min, value, max) => {
+                write!(f, "clamp(")?;
+                list(f, &[min, value, max])
+            }
+        }
+    }
+
+    fn values(&self) -> Vec<&Dimension> {
+        match self {
+            DimensionExpression::Value(dimension) => vec![dimension],
+            DimensionExpression::Number(_) => Vec::new(),
+            DimensionExpression::Add(a, b)
+            | DimensionExpression::Sub(a, b)
+            | DimensionExpression::Mul(a, b)
+            | DimensionExpression::Div(a, b) => {
+                let mut values = a.values();
+                values.append(&mut b.values());
+                values
+            }
+            DimensionExpression::Min(list) | DimensionExpression::Max(list) => list
+                .iter()
+                .flat_map(|expression| expression.values())
+                .collect(),
+            DimensionExpression::Clamp(min, value, max) => {
+                let mut values = min.values();
+                values.append(&mut value.values());
+                values.append(&mut max.values());
+                values
+            }
+        }
+    }
+}
+
+///why Dimension::from_html rejected its input, spans are byte ranges into the untrimmed input
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum DimensionParseError {
+    //the number in front of a unit, or a bare value, didn't parse
+    InvalidNumber {
+        input: String,
+        span: Range<usize>,
+        expected: &'static str,
+    },
+    //a calc function that doesn't exist or got the wrong number of arguments
+    UnknownFunction {
+        input: String,
+        span: Range<usize>,
+        expected: &'static str,
+    },
+    //a missing value or parenthesis, or leftovers after the expression
+    UnexpectedCharacter {
+        input: String,
+        span: Range<usize>,
+        expected: &'static str,
+    },
+}
+
+impl DimensionParseError {
+    pub fn input(&self) -> &str {
+        match self {
+            DimensionParseError::InvalidNumber { input, .. }
+            | DimensionParseError::UnknownFunction { input, .. }
+            | DimensionParseError::UnexpectedCharacter { input, .. } => input,
+        }
+    }
+
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            DimensionParseError::InvalidNumber { span, .. }
+            | DimensionParseError::UnknownFunction { span, .. }
+            | DimensionParseError::UnexpectedCharacter { span, .. } => span.clone(),
+        }
+    }
+
+    pub fn expected(&self) -> &'static str {
+        match self {
+            DimensionParseError::InvalidNumber { expected, .. }
+            | DimensionParseError::UnknownFunction { expected, .. }
+            | DimensionParseError::UnexpectedCharacter { expected, .. } => expected,
+        }
+    }
+}
+
+impl Display for DimensionParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let span = self.span();
+        let problem = match self {
+            DimensionParseError::InvalidNumber { .. } => "invalid number",
+            DimensionParseError::UnknownFunction { .. } => "unknown function",
+            DimensionParseError::UnexpectedCharacter { .. } => "unexpected character",
+        };
+        let found = match &self.input()[span.clone()] {
+            "" => "end of input",
+            found => found,
+        };
+        write!(
+            f,
+            "{} in dimension \"{}\" at {}..{}: found \"{}\", expected {}",
+            problem,
+            self.input(),
+            span.start,
+            span.end,
+            found,
+            self.expected()
+        )
+    }
+}
+
+impl Error for DimensionParseError {}
+
+//recursive descent parser for the inside of calc()
+struct ExpressionParser<'a> {
+    //the untrimmed input, for errors
+    input: &'a str,
+    //byte offset of source inside input
+    offset: usize,
+    source: &'a str,
+    html: &'a str,
+}
+
+impl<'a> ExpressionParser<'a> {
+    fn peek(&mut self) -> Option<char> {
+        self.html = self.html.trim_start();
+        self.html.chars().next()
+    }
+
+    //byte offset of the unparsed rest inside input
+    fn position(&self) -> usize {
+        self.offset + self.source.len() - self.html.len()
+    }
+
+    fn unexpected(&mut self, expected: &'static str) -> DimensionParseError {
+        let start = self.position() + self.html.len() - self.html.trim_start().len();
+        let end = start
+            + self
+                .html
+                .trim_start()
+                .chars()
+                .next()
+                .map_or(0, char::len_utf8);
+        DimensionParseError::UnexpectedCharacter {
+            input: self.input.to_string(),
+            span: start..end,
+            expected,
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.html = &self.html[c.len_utf8()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn sum(&mut self) -> Result<DimensionExpression, DimensionParseError> {
+        let mut expression = self.product()?;
+        loop {
+            if self.eat('+') {
+                expression =
+                    DimensionExpression::Add(Box::new(expression), Box::new(self.product()?));
+            } else if self.eat('-') {
+                expression =
+                    DimensionExpression::Sub(Box::new(expression), Box::new(self.product()?));
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<DimensionExpression, DimensionParseError> {
+        let mut expression = self.factor()?;
+        loop {
+            if self.eat('*') {
+                expression =
+                    DimensionExpression::Mul(Box::new(expression), Box::new(self.factor()?));
+            } else if self.eat('/') {
+                expression =
+                    DimensionExpression::Div(Box::new(expression), Box::new(self.factor()?));
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Vec<DimensionExpression>, DimensionParseError> {
+        let mut arguments = vec![self.sum()?];
+        while self.eat(',') {
+            arguments.push(self.sum()?);
+        }
+        if !self.eat(')') {
+            return Err(self.unexpected("')'"));
+        }
+        Ok(arguments)
+    }
+
+    fn factor(&mut self) -> Result<DimensionExpression, DimensionParseError> {
+        if self.eat('(') {
+            let expression = self.sum()?;
+            if !self.eat(')') {
+                return Err(self.unexpected("')'"));
+            }
+            return Ok(expression);
+        }
+        //a minus right before a number is part of the literal, so -2px stays a single value
+        let negative = self.html.starts_with('-')
+            && self.html[1..].starts_with(|c: char| c.is_ascii_digit() || c == '.');
+        if !negative && self.eat('-') {
+            return Ok(DimensionExpression::Mul(
+                Box::new(DimensionExpression::Number(-1.0)),
+                Box::new(self.factor()?),
+            ));
+        }
+
+        self.peek();
+        let start = self.position();
+        let sign = negative as usize;
+        let length = self.html[sign..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '%'))
+            .map_or(self.html.len(), |length| length + sign);
+        let token = self.html[..length].to_ascii_lowercase();
+        if token.is_empty() {
+            return Err(self.unexpected("a number, dimension or function"));
+        }
+        self.html = &self.html[length..];
+        let span = start..start + length;
+
+        if self.eat('(') {
+            let mut arguments = self.arguments()?;
+            return match (token.as_str(), arguments.len()) {
+                ("calc", 1) => Ok(arguments.remove(0)),
+                ("min", _) => Ok(DimensionExpression::Min(arguments)),
+                ("max", _) => Ok(DimensionExpression::Max(arguments)),
+                ("clamp", 3) => {
+                    let max = arguments.pop().unwrap();
+                    let value = arguments.pop().unwrap();
+                    let min = arguments.pop().unwrap();
+                    Ok(DimensionExpression::Clamp(
+                        Box::new(min),
+                        Box::new(value),
+                        Box::new(max),
+                    ))
+                }
+                _ => Err(DimensionParseError::UnknownFunction {
+                    input: self.input.to_string(),
+                    span,
+                    expected: "calc(a), min(a, ...), max(a, ...) or clamp(min, value, max)",
+                }),
+            };
+        }
+
+        match token.parse::<f64>() {
+            Ok(val) => Ok(DimensionExpression::Number(val)),
+            Err(_) => match Dimension::from_html(&token) {
+                Ok(Dimension::Calc(_)) | Err(_) => Err(DimensionParseError::InvalidNumber {
+                    input: self.input.to_string(),
+                    span,
+                    expected: "a number or dimension",
+                }),
+                Ok(dimension) => Ok(DimensionExpression::Value(dimension)),
+            },
+        }
+    }
 }
 
 /* spell-checker: disable */
 impl Dimension {
-    pub fn from_html(mut html: &str) -> Result<Dimension, &str> {
-        html = html.trim();
+    ///a calc dimension, expressions live for the rest of the program and equal ones are shared
+    pub fn calc(expression: DimensionExpression) -> Dimension {
+        static EXPRESSIONS: Mutex<Vec<&'static DimensionExpression>> = Mutex::new(Vec::new());
+        let mut expressions = EXPRESSIONS.lock().unwrap();
+        match expressions
+            .iter()
+            .find(|interned| ***interned == expression)
+        {
+            Some(interned) => Dimension::Calc(interned),
+            None => {
+                let interned: &'static DimensionExpression = Box::leak(Box::new(expression));
+                expressions.push(interned);
+                Dimension::Calc(interned)
+            }
+        }
+    }
+
+    ///true if the value changes with the parent's size
+    pub(crate) fn is_parent_relative(&self) -> bool {
+        match self {
+            Dimension::Percent(_)
+            | Dimension::PW(_)
+            | Dimension::PH(_)
+            | Dimension::PMin(_)
+            | Dimension::PMax(_) => true,
+            Dimension::Calc(expression) => expression
+                .values()
+                .iter()
+                .any(|dimension| dimension.is_parent_relative()),
+            _ => false,
+        }
+    }
+
+    ///true if the value changes with the renderer's size
+    pub(crate) fn is_renderer_relative(&self) -> bool {
+        match self {
+            Dimension::VW(_) | Dimension::VH(_) | Dimension::VMin(_) | Dimension::VMax(_) => true,
+            Dimension::Calc(expression) => expression
+                .values()
+                .iter()
+                .any(|dimension| dimension.is_renderer_relative()),
+            _ => false,
+        }
+    }
+
+    pub fn from_html(input: &str) -> Result<Dimension, DimensionParseError> {
+        let html = input.trim();
+        let offset = input.len() - input.trim_start().len();
         let lowercase_html = html.to_ascii_lowercase();
+        //the number before a unit of the given length
+        let invalid = |unit_length: usize, expected: &'static str| {
+            let number = &lowercase_html[..lowercase_html.len() - unit_length];
+            let start = offset + number.len() - number.trim_start().len();
+            DimensionParseError::InvalidNumber {
+                input: input.to_string(),
+                span: start..offset + number.trim_end().len(),
+                expected,
+            }
+        };
         match true {
             _ if lowercase_html == "auto" => Ok(Dimension::Auto),
+            _ if ["calc(", "min(", "max(", "clamp("]
+                .iter()
+                .any(|function| lowercase_html.starts_with(function)) =>
+            {
+                let mut parser = ExpressionParser {
+                    input,
+                    offset,
+                    source: &lowercase_html,
+                    html: &lowercase_html,
+                };
+                let expression = parser.factor()?;
+                match parser.peek() {
+                    None => Ok(Dimension::calc(expression)),
+                    Some(_) => Err(parser.unexpected("the end of the calc expression")),
+                }
+            }
             _ if lowercase_html.ends_with("px") => match lowercase_html[..lowercase_html.len() - 2]
                 .trim()
                 .parse::<i64>()
             {
                 Ok(val) => Ok(Self::Pixel(val)),
-                Err(_) => Err("Couldn't parse pixel dimension"),
+                Err(_) => Err(invalid(2, "an integer before px")),
             },
             _ if lowercase_html.ends_with('%') => match lowercase_html[..lowercase_html.len() - 1]
                 .trim()
                 .parse::<f64>()
             {
                 Ok(val) => Ok(Self::Percent(val)),
-                Err(_) => Err("Couldn't parse percent dimension"),
+                Err(_) => Err(invalid(1, "a number before %")),
             },
             _ if lowercase_html.ends_with("vw") => {
                 match lowercase_html[..lowercase_html.len() - 2]
@@ -184,7 +633,7 @@ impl Dimension {
                     .parse::<f64>()
                 {
                     Ok(val) => Ok(Self::VW(val)),
-                    Err(_) => Err("Couldn't parse viewport width dimension"),
+                    Err(_) => Err(invalid(2, "a number before vw")),
                 }
             }
             _ if lowercase_html.ends_with("vh") => {
@@ -194,7 +643,7 @@ impl Dimension {
                     .parse::<f64>()
                 {
                     Ok(val) => Ok(Self::VH(val)),
-                    Err(_) => Err("Couldn't parse viewport height dimension"),
+                    Err(_) => Err(invalid(2, "a number before vh")),
                 }
             }
             _ if lowercase_html.ends_with("vmin") => {
@@ -204,7 +653,7 @@ impl Dimension {
                     .parse::<f64>()
                 {
                     Ok(val) => Ok(Self::VMin(val)),
-                    Err(_) => Err("Couldn't parse viewport minimum dimension"),
+                    Err(_) => Err(invalid(4, "a number before vmin")),
                 }
             }
             _ if lowercase_html.ends_with("vmax") => {
@@ -214,7 +663,7 @@ impl Dimension {
                     .parse::<f64>()
                 {
                     Ok(val) => Ok(Self::VMax(val)),
-                    Err(_) => Err("Couldn't parse viewport maximum dimension"),
+                    Err(_) => Err(invalid(4, "a number before vmax")),
                 }
             }
             _ if lowercase_html.ends_with("pw") => {
@@ -224,7 +673,7 @@ impl Dimension {
                     .parse::<f64>()
                 {
                     Ok(val) => Ok(Self::PW(val)),
-                    Err(_) => Err("Couldn't parse parent width dimension"),
+                    Err(_) => Err(invalid(2, "a number before pw")),
                 }
             }
             _ if lowercase_html.ends_with("ph") => {
@@ -234,7 +683,7 @@ impl Dimension {
                     .parse::<f64>()
                 {
                     Ok(val) => Ok(Self::PH(val)),
-                    Err(_) => Err("Couldn't parse parent height dimension"),
+                    Err(_) => Err(invalid(2, "a number before ph")),
                 }
             }
             _ if lowercase_html.ends_with("pmin") => {
@@ -244,7 +693,7 @@ impl Dimension {
                     .parse::<f64>()
                 {
                     Ok(val) => Ok(Self::PMin(val)),
-                    Err(_) => Err("Couldn't parse parent minimum dimension"),
+                    Err(_) => Err(invalid(4, "a number before pmin")),
                 }
             }
             _ if lowercase_html.ends_with("pmax") => {
@@ -254,12 +703,12 @@ impl Dimension {
                     .parse::<f64>()
                 {
                     Ok(val) => Ok(Self::PMax(val)),
-                    Err(_) => Err("Couldn't parse parent maximum dimension"),
+                    Err(_) => Err(invalid(4, "a number before pmax")),
                 }
             }
             _ => match lowercase_html.parse::<i64>() {
                 Ok(val) => Ok(Self::Pixel(val)),
-                Err(_) => Err("Couldn't parse pixel value"),
+                Err(_) => Err(invalid(0, "auto, an integer, or a number with a unit")),
             },
         }
     }
@@ -0,0 +1,697 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::misc::{
+    read_stdin, stdin_ready, write_escape, ResizeWatcher, TerminalMode, RESIZE_SETTLE_TIME,
+};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Key {
+    Char(char),
+    Enter,
+    Tab,
+    BackTab,
+    Backspace,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    F(u8),
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub alt: bool,
+    pub control: bool,
+    pub super_key: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers {
+        shift: false,
+        alt: false,
+        control: false,
+        super_key: false,
+    };
+
+    //xterm and kitty style modifier parameter, 1 + bitmask
+    pub(crate) fn from_parameter(parameter: u32) -> Modifiers {
+        let mask = parameter.saturating_sub(1);
+        Modifiers {
+            shift: mask & 1 != 0,
+            alt: mask & 2 != 0,
+            control: mask & 4 != 0,
+            super_key: mask & 8 != 0,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum KeyEventKind {
+    Press,
+    Repeat,
+    Release,
+}
+
+impl KeyEventKind {
+    pub(crate) fn from_parameter(parameter: u32) -> KeyEventKind {
+        match parameter {
+            2 => KeyEventKind::Repeat,
+            3 => KeyEventKind::Release,
+            _ => KeyEventKind::Press,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct KeyEvent {
+    pub key: Key,
+    pub modifiers: Modifiers,
+    pub kind: KeyEventKind,
+}
+
+impl KeyEvent {
+    pub fn new(key: Key, modifiers: Modifiers) -> KeyEvent {
+        KeyEvent {
+            key,
+            modifiers,
+            kind: KeyEventKind::Press,
+        }
+    }
+
+    pub fn with_kind(&self, kind: KeyEventKind) -> KeyEvent {
+        KeyEvent { kind, ..*self }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+    None,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum MouseEventKind {
+    Press,
+    Release,
+    Drag,
+    Move,
+    ScrollUp,
+    ScrollDown,
+    ScrollLeft,
+    ScrollRight,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct MouseEvent {
+    pub kind: MouseEventKind,
+    pub button: MouseButton,
+    pub x: i64,
+    pub y: i64,
+    pub modifiers: Modifiers,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum InputEvent {
+    Key(KeyEvent),
+    Mouse(MouseEvent),
+    //sent once the terminal size stopped changing
+    Resize { width: i64, height: i64 },
+    Paste(String),
+    FocusGained,
+    FocusLost,
+}
+
+pub struct InputParser {
+    buffer: Vec<u8>,
+    keyboard_enhancement: Option<u32>,
+    cell_size: Option<(u32, u32)>,
+}
+
+impl InputParser {
+    pub fn new() -> InputParser {
+        InputParser {
+            buffer: Vec::new(),
+            keyboard_enhancement: None,
+            cell_size: None,
+        }
+    }
+
+    ///parses as many complete events as possible, keeping incomplete sequences for the next call
+    pub fn parse(&mut self, bytes: &[u8]) -> Vec<InputEvent> {
+        self.buffer.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        let mut position = 0;
+        while position < self.buffer.len() {
+            match parse_event(&self.buffer[position..]) {
+                Some((event, length)) => {
+                    if let Some(flags) =
+                        keyboard_enhancement_reply(&self.buffer[position..position + length])
+                    {
+                        self.keyboard_enhancement = Some(flags);
+                    }
+                    if let Some(cell_size) =
+                        cell_size_reply(&self.buffer[position..position + length])
+                    {
+                        self.cell_size = Some(cell_size);
+                    }
+                    if let Some(event) = event {
+                        events.push(event);
+                    }
+                    position += length;
+                }
+                None => break,
+            }
+        }
+        self.buffer.drain(..position);
+        events
+    }
+
+    ///resolves leftover bytes once no more input is coming, a lone escape becomes the escape key
+    pub fn flush(&mut self) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+        //a slow paste is still one event, wait for its end marker
+        if self.buffer.starts_with(PASTE_START) {
+            return events;
+        }
+        if self.buffer.first() == Some(&0x1b) {
+            events.push(InputEvent::Key(KeyEvent::new(Key::Escape, Modifiers::NONE)));
+            self.buffer.remove(0);
+            events.append(&mut self.parse(&[]));
+        }
+        self.buffer.clear();
+        events
+    }
+
+    pub fn has_pending(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    ///flags reported by the terminal after a kitty keyboard query, None if it never answered
+    pub fn keyboard_enhancement(&self) -> Option<u32> {
+        self.keyboard_enhancement
+    }
+
+    ///cell size in pixels as (width, height) reported after a CSI 16 t query
+    pub fn cell_size(&self) -> Option<(u32, u32)> {
+        self.cell_size
+    }
+}
+
+impl Default for InputParser {
+    fn default() -> Self {
+        InputParser::new()
+    }
+}
+
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+fn key(key: Key, modifiers: Modifiers) -> Option<InputEvent> {
+    Some(InputEvent::Key(KeyEvent::new(key, modifiers)))
+}
+
+///returns None while the sequence is incomplete, otherwise the event and the consumed length
+fn parse_event(bytes: &[u8]) -> Option<(Option<InputEvent>, usize)> {
+    match bytes[0] {
+        0x1b => match bytes.get(1) {
+            None => None,
+            Some(b'[') if bytes.starts_with(PASTE_START) => parse_paste(bytes),
+            Some(b'[') => parse_csi(bytes),
+            Some(b'O') => bytes
+                .get(2)
+                .map(|final_byte| (ss3_key(*final_byte, Modifiers::NONE), 3)),
+            Some(0x1b) => Some((key(Key::Escape, Modifiers::NONE), 1)),
+            Some(_) => {
+                let (event, length) = parse_event(&bytes[1..])?;
+                let event = match event {
+                    Some(InputEvent::Key(mut key_event)) => {
+                        key_event.modifiers.alt = true;
+                        Some(InputEvent::Key(key_event))
+                    }
+                    event => event,
+                };
+                Some((event, length + 1))
+            }
+        },
+        b'\r' | b'\n' => Some((key(Key::Enter, Modifiers::NONE), 1)),
+        b'\t' => Some((key(Key::Tab, Modifiers::NONE), 1)),
+        0x7f | 0x08 => Some((key(Key::Backspace, Modifiers::NONE), 1)),
+        0x00 => Some((
+            key(
+                Key::Char(' '),
+                Modifiers {
+                    control: true,
+                    ..Modifiers::NONE
+                },
+            ),
+            1,
+        )),
+        byte @ 0x01..=0x1a => Some((
+            key(
+                Key::Char((byte - 1 + b'a') as char),
+                Modifiers {
+                    control: true,
+                    ..Modifiers::NONE
+                },
+            ),
+            1,
+        )),
+        0x1c..=0x1f => Some((None, 1)),
+        _ => {
+            let length = match bytes[0] {
+                0xf0..=0xf7 => 4,
+                0xe0..=0xef => 3,
+                0xc0..=0xdf => 2,
+                _ => 1,
+            };
+            if bytes.len() < length {
+                return None;
+            }
+            match std::str::from_utf8(&bytes[..length]) {
+                Ok(text) => Some((
+                    text.chars()
+                        .next()
+                        .and_then(|c| key(Key::Char(c), Modifiers::NONE)),
+                    length,
+                )),
+                Err(_) => Some((None, 1)),
+            }
+        }
+    }
+}
+
+//bracketed paste: CSI 200 ~ text CSI 201 ~, the terminal sends line breaks as carriage returns
+fn parse_paste(bytes: &[u8]) -> Option<(Option<InputEvent>, usize)> {
+    let content = &bytes[PASTE_START.len()..];
+    let end = content
+        .windows(PASTE_END.len())
+        .position(|window| window == PASTE_END)?;
+    let text = String::from_utf8_lossy(&content[..end])
+        .replace("\r\n", "\n")
+        .replace('\r', "\n");
+    Some((
+        Some(InputEvent::Paste(text)),
+        PASTE_START.len() + end + PASTE_END.len(),
+    ))
+}
+
+fn parse_csi(bytes: &[u8]) -> Option<(Option<InputEvent>, usize)> {
+    //parameter and intermediate bytes, then a single final byte
+    let mut end = 2;
+    while end < bytes.len() && (0x20..=0x3f).contains(&bytes[end]) {
+        end += 1;
+    }
+    if end >= bytes.len() {
+        return None;
+    }
+    let final_byte = bytes[end];
+    let parameters = std::str::from_utf8(&bytes[2..end]).unwrap_or("");
+    Some((csi_event(parameters, final_byte), end + 1))
+}
+
+//the kitty protocol answers a CSI ? u query with CSI ? flags u
+fn keyboard_enhancement_reply(sequence: &[u8]) -> Option<u32> {
+    let flags = sequence.strip_prefix(b"\x1b[?")?.strip_suffix(b"u")?;
+    std::str::from_utf8(flags).ok()?.parse::<u32>().ok()
+}
+
+//a CSI 16 t query is answered with CSI 6 ; height ; width t
+fn cell_size_reply(sequence: &[u8]) -> Option<(u32, u32)> {
+    let size = sequence.strip_prefix(b"\x1b[6;")?.strip_suffix(b"t")?;
+    let (height, width) = std::str::from_utf8(size).ok()?.split_once(';')?;
+    match (width.parse::<u32>().ok()?, height.parse::<u32>().ok()?) {
+        (0, _) | (_, 0) => None,
+        size => Some(size),
+    }
+}
+
+fn parse_parameters(parameters: &str) -> Vec<u32> {
+    parameters
+        .split(';')
+        .map(|parameter| parameter.parse::<u32>().unwrap_or(0))
+        .collect()
+}
+
+//kitty adds colon separated sub-parameters, like modifiers:event_type
+fn parse_sub_parameters(parameters: &str) -> Vec<Vec<u32>> {
+    parameters
+        .split(';')
+        .map(|parameter| {
+            parameter
+                .split(':')
+                .map(|value| value.parse::<u32>().unwrap_or(0))
+                .collect()
+        })
+        .collect()
+}
+
+fn csi_event(parameters: &str, final_byte: u8) -> Option<InputEvent> {
+    if let Some(mouse_parameters) = parameters.strip_prefix('<') {
+        return sgr_mouse_event(mouse_parameters, final_byte);
+    }
+    if parameters.starts_with('?') {
+        return None;
+    }
+    match (parameters, final_byte) {
+        ("", b'I') => return Some(InputEvent::FocusGained),
+        ("", b'O') => return Some(InputEvent::FocusLost),
+        _ => (),
+    }
+
+    let values = parse_sub_parameters(parameters);
+    let modifiers = Modifiers::from_parameter(values.get(1).map_or(1, |value| value[0]));
+    let kind = KeyEventKind::from_parameter(
+        values
+            .get(1)
+            .and_then(|value| value.get(1).copied())
+            .unwrap_or(1),
+    );
+    let event = match final_byte {
+        b'u' => kitty_key(values[0][0], modifiers),
+        b'~' => {
+            let key_code = match values[0][0] {
+                1 | 7 => Key::Home,
+                2 => Key::Insert,
+                3 => Key::Delete,
+                4 | 8 => Key::End,
+                5 => Key::PageUp,
+                6 => Key::PageDown,
+                value @ 11..=15 => Key::F((value - 10) as u8),
+                value @ 17..=21 => Key::F((value - 11) as u8),
+                value @ 23..=24 => Key::F((value - 12) as u8),
+                _ => return None,
+            };
+            key(key_code, modifiers)
+        }
+        b'Z' => key(Key::BackTab, modifiers),
+        _ => ss3_key(final_byte, modifiers),
+    };
+    match event {
+        Some(InputEvent::Key(key_event)) => Some(InputEvent::Key(key_event.with_kind(kind))),
+        event => event,
+    }
+}
+
+//CSI code ; modifiers u, codes are unicode values or kitty's private use area functional keys
+fn kitty_key(code: u32, modifiers: Modifiers) -> Option<InputEvent> {
+    let key_code = match code {
+        9 => Key::Tab,
+        13 | 57414 => Key::Enter,
+        27 => Key::Escape,
+        127 => Key::Backspace,
+        57399..=57408 => Key::Char(char::from_digit(code - 57399, 10)?),
+        57409 => Key::Char('.'),
+        57410 => Key::Char('/'),
+        57411 => Key::Char('*'),
+        57412 => Key::Char('-'),
+        57413 => Key::Char('+'),
+        57415 => Key::Char('='),
+        57417 => Key::Left,
+        57418 => Key::Right,
+        57419 => Key::Up,
+        57420 => Key::Down,
+        57421 => Key::PageUp,
+        57422 => Key::PageDown,
+        57423 => Key::Home,
+        57424 => Key::End,
+        57425 => Key::Insert,
+        57426 => Key::Delete,
+        57376..=57398 => Key::F((code - 57376 + 13) as u8),
+        //lock, media and lone modifier keys
+        57344..=63743 => return None,
+        _ => Key::Char(char::from_u32(code)?),
+    };
+    key(key_code, modifiers)
+}
+
+fn ss3_key(final_byte: u8, modifiers: Modifiers) -> Option<InputEvent> {
+    let key_code = match final_byte {
+        b'A' => Key::Up,
+        b'B' => Key::Down,
+        b'C' => Key::Right,
+        b'D' => Key::Left,
+        b'H' => Key::Home,
+        b'F' => Key::End,
+        b'P' => Key::F(1),
+        b'Q' => Key::F(2),
+        b'R' => Key::F(3),
+        b'S' => Key::F(4),
+        _ => return None,
+    };
+    key(key_code, modifiers)
+}
+
+//SGR 1006 encoding: CSI < button ; x ; y M (press) or m (release)
+fn sgr_mouse_event(parameters: &str, final_byte: u8) -> Option<InputEvent> {
+    let values = parse_parameters(parameters);
+    if values.len() != 3 || (final_byte != b'M' && final_byte != b'm') {
+        return None;
+    }
+    let code = values[0];
+    let modifiers = Modifiers {
+        shift: code & 4 != 0,
+        alt: code & 8 != 0,
+        control: code & 16 != 0,
+        super_key: false,
+    };
+    let button = match code & 3 {
+        0 => MouseButton::Left,
+        1 => MouseButton::Middle,
+        2 => MouseButton::Right,
+        _ => MouseButton::None,
+    };
+    let (kind, button) = match (code & 64 != 0, code & 32 != 0, final_byte) {
+        (true, _, _) => (
+            match code & 3 {
+                0 => MouseEventKind::ScrollUp,
+                1 => MouseEventKind::ScrollDown,
+                2 => MouseEventKind::ScrollLeft,
+                _ => MouseEventKind::ScrollRight,
+            },
+            MouseButton::None,
+        ),
+        (false, true, _) if button == MouseButton::None => (MouseEventKind::Move, button),
+        (false, true, _) => (MouseEventKind::Drag, button),
+        (false, false, b'm') => (MouseEventKind::Release, button),
+        (false, false, _) => (MouseEventKind::Press, button),
+    };
+    Some(InputEvent::Mouse(MouseEvent {
+        kind,
+        button,
+        x: values[1] as i64 - 1,
+        y: values[2] as i64 - 1,
+        modifiers,
+    }))
+}
+
+pub struct Input {
+    receiver: Receiver<InputEvent>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    terminal_mode: Option<TerminalMode>,
+    mouse: bool,
+    bracketed_paste: bool,
+    focus_events: bool,
+    kitty_keyboard: bool,
+    kitty_keyboard_supported: Arc<AtomicBool>,
+    cell_size: Arc<Mutex<Option<(u32, u32)>>>,
+}
+
+impl Input {
+    ///puts the terminal in raw mode and starts reading stdin on a separate thread
+    pub fn new() -> Input {
+        let terminal_mode = TerminalMode::enable_raw_mode();
+        let running = Arc::new(AtomicBool::new(true));
+        let kitty_keyboard_supported = Arc::new(AtomicBool::new(false));
+        let cell_size = Arc::new(Mutex::new(None));
+        let (sender, receiver) = mpsc::channel();
+        let thread = {
+            let running = running.clone();
+            let kitty_keyboard_supported = kitty_keyboard_supported.clone();
+            let cell_size = cell_size.clone();
+            thread::spawn(move || {
+                let mut parser = InputParser::new();
+                let mut buffer = [0u8; 1024];
+                let mut resize_watcher = ResizeWatcher::new(RESIZE_SETTLE_TIME);
+                resize_watcher.poll();
+                while running.load(Ordering::Relaxed) {
+                    if let Some((width, height)) = resize_watcher.poll() {
+                        if sender.send(InputEvent::Resize { width, height }).is_err() {
+                            return;
+                        }
+                    }
+                    let events = if stdin_ready(Duration::from_millis(25)) {
+                        let count = read_stdin(&mut buffer);
+                        if count == 0 {
+                            break;
+                        }
+                        parser.parse(&buffer[..count])
+                    } else if parser.has_pending() {
+                        parser.flush()
+                    } else {
+                        continue;
+                    };
+                    if parser.keyboard_enhancement().is_some() {
+                        kitty_keyboard_supported.store(true, Ordering::Relaxed);
+                    }
+                    if parser.cell_size().is_some() {
+                        *cell_size.lock().unwrap() = parser.cell_size();
+                    }
+                    for event in events {
+                        if sender.send(event).is_err() {
+                            return;
+                        }
+                    }
+                }
+            })
+        };
+        Input {
+            receiver,
+            running,
+            thread: Some(thread),
+            terminal_mode,
+            mouse: false,
+            bracketed_paste: false,
+            focus_events: false,
+            kitty_keyboard: false,
+            kitty_keyboard_supported,
+            cell_size,
+        }
+    }
+
+    ///enables press, release, drag and motion reporting in the SGR 1006 format
+    pub fn enable_mouse(&mut self) -> &mut Self {
+        write_escape("\x1b[?1000h\x1b[?1002h\x1b[?1003h\x1b[?1006h");
+        self.mouse = true;
+        self
+    }
+
+    pub fn disable_mouse(&mut self) -> &mut Self {
+        write_escape("\x1b[?1006l\x1b[?1003l\x1b[?1002l\x1b[?1000l");
+        self.mouse = false;
+        self
+    }
+
+    ///pasted text arrives as a single Paste event instead of separate keys
+    pub fn enable_bracketed_paste(&mut self) -> &mut Self {
+        write_escape("\x1b[?2004h");
+        self.bracketed_paste = true;
+        self
+    }
+
+    pub fn disable_bracketed_paste(&mut self) -> &mut Self {
+        write_escape("\x1b[?2004l");
+        self.bracketed_paste = false;
+        self
+    }
+
+    ///reports FocusGained and FocusLost when the terminal window gains or loses focus
+    pub fn enable_focus_events(&mut self) -> &mut Self {
+        write_escape("\x1b[?1004h");
+        self.focus_events = true;
+        self
+    }
+
+    pub fn disable_focus_events(&mut self) -> &mut Self {
+        write_escape("\x1b[?1004l");
+        self.focus_events = false;
+        self
+    }
+
+    ///pushes the kitty keyboard flags for disambiguated keys and event types, then queries support
+    ///terminals without the protocol ignore both and keep sending legacy sequences
+    pub fn enable_kitty_keyboard(&mut self) -> &mut Self {
+        write_escape("\x1b[>3u\x1b[?u");
+        self.kitty_keyboard = true;
+        self
+    }
+
+    pub fn disable_kitty_keyboard(&mut self) -> &mut Self {
+        write_escape("\x1b[<u");
+        self.kitty_keyboard = false;
+        self
+    }
+
+    ///true once the terminal has answered the kitty keyboard query
+    pub fn kitty_keyboard_supported(&self) -> bool {
+        self.kitty_keyboard_supported.load(Ordering::Relaxed)
+    }
+
+    ///asks the terminal for its cell size in pixels, see cell_aspect for the answer
+    pub fn query_cell_size(&mut self) -> &mut Self {
+        write_escape("\x1b[16t");
+        self
+    }
+
+    ///how many times taller than wide a cell is, None until the terminal answered the query
+    pub fn cell_aspect(&self) -> Option<f64> {
+        self.cell_size
+            .lock()
+            .unwrap()
+            .map(|(width, height)| height as f64 / width as f64)
+    }
+
+    pub fn poll(&self) -> Option<InputEvent> {
+        self.receiver.try_recv().ok()
+    }
+
+    pub fn wait(&self, timeout: Duration) -> Option<InputEvent> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    pub fn stop(mut self) {
+        self.restore();
+    }
+
+    fn restore(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+        if self.mouse {
+            self.disable_mouse();
+        }
+        if self.bracketed_paste {
+            self.disable_bracketed_paste();
+        }
+        if self.focus_events {
+            self.disable_focus_events();
+        }
+        if self.kitty_keyboard {
+            self.disable_kitty_keyboard();
+        }
+        if let Some(terminal_mode) = self.terminal_mode.take() {
+            terminal_mode.restore();
+        }
+    }
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Input::new()
+    }
+}
+
+impl Drop for Input {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
@@ -0,0 +1,398 @@
+use crate::renderer_object_style::{
+    AlignItems, FlexDirection, FlexLayout, GridPlacement, JustifyContent,
+};
+
+//a child as seen by the layout, sizes include the border
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub(crate) struct LayoutItem {
+    pub(crate) width: i64,
+    pub(crate) height: i64,
+    pub(crate) grow: f64,
+    pub(crate) shrink: f64,
+    pub(crate) stretch_width: bool,
+    pub(crate) stretch_height: bool,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub(crate) struct Placement {
+    pub(crate) x: i64,
+    pub(crate) y: i64,
+    pub(crate) width: i64,
+    pub(crate) height: i64,
+}
+
+///splits amount proportionally to the weights, the rounding remainder goes to the largest fractions,
+///negative and non finite weights get nothing
+pub(crate) fn distribute(amount: i64, weights: &[f64]) -> Vec<i64> {
+    let weights: Vec<f64> = weights
+        .iter()
+        .map(|weight| match weight.is_finite() {
+            true => weight.max(0.0),
+            false => 0.0,
+        })
+        .collect();
+    let largest = weights
+        .iter()
+        .fold(0.0, |largest: f64, weight| largest.max(*weight));
+    if largest <= 0.0 || amount == 0 {
+        return vec![0; weights.len()];
+    }
+    //scaled down first so huge weights can't add up to infinity
+    let weights: Vec<f64> = weights.iter().map(|weight| weight / largest).collect();
+    let total: f64 = weights.iter().sum();
+    let exact: Vec<f64> = weights
+        .iter()
+        .map(|weight| amount as f64 * weight / total)
+        .collect();
+    let mut shares: Vec<i64> = exact.iter().map(|share| share.floor() as i64).collect();
+    let mut order: Vec<usize> = (0..weights.len()).collect();
+    order.sort_by(|a, b| {
+        (exact[*b] - exact[*b].floor()).total_cmp(&(exact[*a] - exact[*a].floor()))
+    });
+    let remainder = amount - shares.iter().sum::<i64>();
+    for index in order.into_iter().take(remainder.max(0) as usize) {
+        shares[index] += 1;
+    }
+    shares
+}
+
+//grid track with its dimension already resolved
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub(crate) enum Track {
+    Fixed(i64),
+    Auto,
+    Fr(f64),
+}
+
+fn axes(direction: FlexDirection, width: i64, height: i64) -> (i64, i64) {
+    match direction {
+        FlexDirection::Row => (width, height),
+        FlexDirection::Column => (height, width),
+    }
+}
+
+///size of a flex container that fits all items on a single line
+pub(crate) fn flex_content_size(layout: &FlexLayout, gap: i64, items: &[LayoutItem]) -> (i64, i64) {
+    let mut main: i64 = 0;
+    let mut cross: i64 = 0;
+    for item in items {
+        let (item_main, item_cross) = axes(layout.direction, item.width, item.height);
+        main += item_main;
+        cross = cross.max(item_cross);
+    }
+    main += gap * (items.len() as i64 - 1).max(0);
+    axes(layout.direction, main, cross)
+}
+
+pub(crate) fn flex(
+    layout: &FlexLayout,
+    width: i64,
+    height: i64,
+    gap: i64,
+    items: &[LayoutItem],
+) -> Vec<Placement> {
+    let (main_size, cross_size) = axes(layout.direction, width, height);
+    let stretches = |item: &LayoutItem| match layout.direction {
+        FlexDirection::Row => item.stretch_height,
+        FlexDirection::Column => item.stretch_width,
+    };
+
+    let mut lines: Vec<(usize, usize)> = Vec::new();
+    let mut line_start: usize = 0;
+    let mut used: i64 = 0;
+    for (index, item) in items.iter().enumerate() {
+        let item_main = axes(layout.direction, item.width, item.height).0;
+        if index == line_start {
+            used = item_main;
+        } else if layout.wrap && used + gap + item_main > main_size {
+            lines.push((line_start, index));
+            line_start = index;
+            used = item_main;
+        } else {
+            used += gap + item_main;
+        }
+    }
+    if line_start < items.len() {
+        lines.push((line_start, items.len()));
+    }
+
+    let mut placements: Vec<Placement> = Vec::with_capacity(items.len());
+    let mut line_position: i64 = 0;
+    for (start, end) in lines {
+        let line = &items[start..end];
+        let count = line.len() as i64;
+        let mut sizes: Vec<(i64, i64)> = line
+            .iter()
+            .map(|item| axes(layout.direction, item.width, item.height))
+            .collect();
+
+        let free = main_size - sizes.iter().map(|size| size.0).sum::<i64>() - gap * (count - 1);
+        if free > 0 {
+            let grow: Vec<f64> = line.iter().map(|item| item.grow.max(0.0)).collect();
+            for (size, share) in sizes.iter_mut().zip(distribute(free, &grow)) {
+                size.0 += share;
+            }
+        } else if free < 0 {
+            //like css, bigger items give up more space
+            let shrink: Vec<f64> = line
+                .iter()
+                .zip(&sizes)
+                .map(|(item, size)| item.shrink.max(0.0) * size.0 as f64)
+                .collect();
+            for (size, share) in sizes.iter_mut().zip(distribute(-free, &shrink)) {
+                size.0 = (size.0 - share).max(0);
+            }
+        }
+
+        let remaining =
+            main_size - sizes.iter().map(|size| size.0).sum::<i64>() - gap * (count - 1);
+        let spare = remaining.max(0) as f64;
+        let (offset, spacing): (f64, f64) = match layout.justify_content {
+            JustifyContent::Start => (0.0, 0.0),
+            JustifyContent::Center => (remaining as f64 / 2.0, 0.0),
+            JustifyContent::End => (remaining as f64, 0.0),
+            JustifyContent::SpaceBetween if count > 1 => (0.0, spare / (count - 1) as f64),
+            JustifyContent::SpaceBetween => (0.0, 0.0),
+            JustifyContent::SpaceAround => (spare / count as f64 / 2.0, spare / count as f64),
+            JustifyContent::SpaceEvenly => (spare / (count + 1) as f64, spare / (count + 1) as f64),
+        };
+
+        //a single line fills the container, wrapped lines are as tall as their tallest item
+        let line_cross = if layout.wrap {
+            sizes.iter().map(|size| size.1).max().unwrap_or(0)
+        } else {
+            cross_size
+        };
+
+        let mut main_position: i64 = 0;
+        for (index, (item, size)) in line.iter().zip(&sizes).enumerate() {
+            let item_cross = if layout.align_items == AlignItems::Stretch && stretches(item) {
+                line_cross
+            } else {
+                size.1
+            };
+            let cross_offset = match layout.align_items {
+                AlignItems::Start | AlignItems::Stretch => 0,
+                AlignItems::Center => (line_cross - item_cross) / 2,
+                AlignItems::End => line_cross - item_cross,
+            };
+            let main = main_position + (offset + spacing * index as f64).round() as i64;
+            let cross = line_position + cross_offset;
+            main_position += size.0 + gap;
+
+            let (x, y) = axes(layout.direction, main, cross);
+            let (width, height) = axes(layout.direction, size.0, item_cross);
+            placements.push(Placement {
+                x,
+                y,
+                width,
+                height,
+            });
+        }
+        line_position += line_cross + gap;
+    }
+    placements
+}
+
+///fills in the cells of children without a placement, row by row, skipping taken cells
+pub(crate) fn grid_cells(
+    placements: &[Option<GridPlacement>],
+    column_count: usize,
+) -> Vec<GridPlacement> {
+    let column_count = column_count.max(1);
+    let mut taken: Vec<GridPlacement> = placements.iter().flatten().copied().collect();
+    let is_taken = |taken: &Vec<GridPlacement>, row: usize, column: usize| {
+        taken.iter().any(|cell| {
+            (cell.row..cell.row + cell.row_span).contains(&row)
+                && (cell.column..cell.column + cell.column_span).contains(&column)
+        })
+    };
+
+    let mut cursor: usize = 0;
+    let mut cells: Vec<GridPlacement> = Vec::with_capacity(placements.len());
+    for placement in placements {
+        match placement {
+            Some(cell) => cells.push(*cell),
+            None => {
+                while is_taken(&taken, cursor / column_count, cursor % column_count) {
+                    cursor += 1;
+                }
+                let cell = GridPlacement::new(cursor / column_count, cursor % column_count);
+                taken.push(cell);
+                cells.push(cell);
+            }
+        }
+    }
+    cells
+}
+
+///sizes of the tracks along one axis, items are (first track, span, size),
+///without available space fractional tracks fit their content
+fn track_sizes(
+    tracks: &[Track],
+    available: Option<i64>,
+    gap: i64,
+    items: &[(usize, usize, i64)],
+) -> Vec<i64> {
+    let count = items
+        .iter()
+        .map(|(start, span, _)| start + span)
+        .fold(tracks.len(), usize::max);
+    let tracks: Vec<Track> = (0..count)
+        .map(|index| match (tracks.get(index), available) {
+            (Some(Track::Fr(_)), None) | (None, _) => Track::Auto,
+            (Some(track), _) => *track,
+        })
+        .collect();
+
+    let mut sizes: Vec<i64> = tracks
+        .iter()
+        .map(|track| match track {
+            Track::Fixed(size) => (*size).max(0),
+            _ => 0,
+        })
+        .collect();
+
+    //single track items first, spanning items only grow the auto tracks they cover
+    let mut ordered: Vec<&(usize, usize, i64)> = items.iter().collect();
+    ordered.sort_by_key(|(_, span, _)| *span);
+    for (start, span, size) in ordered {
+        let range = *start..start + span;
+        let current: i64 = sizes[range.clone()].iter().sum::<i64>() + gap * (*span as i64 - 1);
+        let weights: Vec<f64> = tracks[range.clone()]
+            .iter()
+            .map(|track| (*track == Track::Auto) as i64 as f64)
+            .collect();
+        for (track_size, share) in sizes[range]
+            .iter_mut()
+            .zip(distribute((size - current).max(0), &weights))
+        {
+            *track_size += share;
+        }
+    }
+
+    if let Some(available) = available {
+        let used: i64 = sizes.iter().sum::<i64>() + gap * (count as i64 - 1).max(0);
+        let weights: Vec<f64> = tracks
+            .iter()
+            .map(|track| match track {
+                Track::Fr(fraction) => fraction.max(0.0),
+                _ => 0.0,
+            })
+            .collect();
+        for (size, share) in sizes
+            .iter_mut()
+            .zip(distribute((available - used).max(0), &weights))
+        {
+            *size += share;
+        }
+    }
+    sizes
+}
+
+fn track_positions(sizes: &[i64], gap: i64) -> Vec<i64> {
+    let mut position: i64 = 0;
+    sizes
+        .iter()
+        .map(|size| {
+            let start = position;
+            position += size + gap;
+            start
+        })
+        .collect()
+}
+
+fn grid_track_sizes(
+    columns: &[Track],
+    rows: &[Track],
+    space: Option<(i64, i64)>,
+    (column_gap, row_gap): (i64, i64),
+    items: &[LayoutItem],
+    cells: &[GridPlacement],
+) -> (Vec<i64>, Vec<i64>) {
+    let column_items: Vec<(usize, usize, i64)> = cells
+        .iter()
+        .zip(items)
+        .map(|(cell, item)| (cell.column, cell.column_span, item.width))
+        .collect();
+    let row_items: Vec<(usize, usize, i64)> = cells
+        .iter()
+        .zip(items)
+        .map(|(cell, item)| (cell.row, cell.row_span, item.height))
+        .collect();
+    (
+        track_sizes(
+            columns,
+            space.map(|space| space.0),
+            column_gap,
+            &column_items,
+        ),
+        track_sizes(rows, space.map(|space| space.1), row_gap, &row_items),
+    )
+}
+
+///size of a grid container whose fractional tracks fit their content
+pub(crate) fn grid_content_size(
+    columns: &[Track],
+    rows: &[Track],
+    gaps: (i64, i64),
+    items: &[LayoutItem],
+    cells: &[GridPlacement],
+) -> (i64, i64) {
+    let (column_sizes, row_sizes) = grid_track_sizes(columns, rows, None, gaps, items, cells);
+    let total = |sizes: &Vec<i64>, gap: i64| {
+        sizes.iter().sum::<i64>() + gap * (sizes.len() as i64 - 1).max(0)
+    };
+    (total(&column_sizes, gaps.0), total(&row_sizes, gaps.1))
+}
+
+pub(crate) fn grid(
+    columns: &[Track],
+    rows: &[Track],
+    width: i64,
+    height: i64,
+    (column_gap, row_gap): (i64, i64),
+    items: &[LayoutItem],
+    cells: &[GridPlacement],
+) -> Vec<Placement> {
+    let (column_sizes, row_sizes) = grid_track_sizes(
+        columns,
+        rows,
+        Some((width, height)),
+        (column_gap, row_gap),
+        items,
+        cells,
+    );
+    let column_positions = track_positions(&column_sizes, column_gap);
+    let row_positions = track_positions(&row_sizes, row_gap);
+
+    //auto sized children fill their area, the rest keep their size in its top left corner
+    items
+        .iter()
+        .zip(cells)
+        .map(|(item, cell)| {
+            let area_width = column_sizes[cell.column..cell.column + cell.column_span]
+                .iter()
+                .sum::<i64>()
+                + column_gap * (cell.column_span as i64 - 1);
+            let area_height = row_sizes[cell.row..cell.row + cell.row_span]
+                .iter()
+                .sum::<i64>()
+                + row_gap * (cell.row_span as i64 - 1);
+            Placement {
+                x: column_positions[cell.column],
+                y: row_positions[cell.row],
+                width: if item.stretch_width {
+                    area_width
+                } else {
+                    item.width
+                },
+                height: if item.stretch_height {
+                    area_height
+                } else {
+                    item.height
+                },
+            }
+        })
+        .collect()
+}
@@ -1,7 +1,11 @@
 pub mod color;
 pub mod color_area;
+mod color_space;
 pub mod colors;
+pub mod focus;
 pub mod geometry;
+pub mod input;
+mod layout;
 pub mod misc;
 pub mod pixel;
 pub mod renderer;
@@ -10,3 +14,5 @@ pub mod renderer_object_style;
 pub mod renderer_object_value;
 pub mod renderer_object_wrapper;
 pub mod tests;
+mod text;
+pub mod viewport;
@@ -1,12 +1,18 @@
-use std::io::{self, Write};
+use std::{
+    io::{self, Write},
+    num::ParseFloatError,
+    time::{Duration, Instant},
+};
+
+use terminal_size::terminal_size;
 
 use crate::{
     colors::Colors,
-    geometry::Dimension,
+    geometry::{Dimension, DimensionExpression},
     pixel::{Pixel, Pixels},
 };
 
-pub(crate) fn process_percent(mut percent_double: &str) -> Result<f64, &str> {
+pub(crate) fn process_percent(mut percent_double: &str) -> Result<f64, ParseFloatError> {
     percent_double = percent_double.trim();
     let alpha_mod = if percent_double.ends_with('%') {
         percent_double = percent_double[0..percent_double.len() - 1].trim();
@@ -14,10 +20,7 @@ pub(crate) fn process_percent(mut percent_double: &str) -> Result<f64, &str> {
     } else {
         1.0
     };
-    match percent_double.parse::<f64>() {
-        Ok(val) => Ok(val * alpha_mod),
-        Err(_) => Err("Couldn't parse percent"),
-    }
+    percent_double.parse::<f64>().map(|val| val * alpha_mod)
 }
 
 pub(crate) fn generic_dimension_calc(
@@ -28,33 +31,98 @@ pub(crate) fn generic_dimension_calc(
     renderer_height: i64,
     horizontal: bool,
 ) -> i64 {
+    dimension_value(
+        dim,
+        parent_width as f64,
+        parent_height as f64,
+        renderer_width as f64,
+        renderer_height as f64,
+        horizontal,
+    )
+    .round() as i64
+}
+
+//unrounded, so calc expressions only round once
+fn dimension_value(
+    dim: &Dimension,
+    parent_width: f64,
+    parent_height: f64,
+    renderer_width: f64,
+    renderer_height: f64,
+    horizontal: bool,
+) -> f64 {
     match dim {
-        Dimension::Auto => 0,
-        Dimension::Pixel(val) => *val,
-        Dimension::Percent(val) => (if horizontal {
-            parent_width
-        } else {
-            parent_height
-        } as f64
-            * val
-            * 0.01)
-            .round() as i64,
-        Dimension::PW(val) => (parent_width as f64 * val * 0.01).round() as i64,
-        Dimension::PH(val) => (parent_height as f64 * val * 0.01).round() as i64,
-        Dimension::PMin(val) => {
-            (parent_width.min(parent_height) as f64 * val * 0.01).round() as i64
-        }
-        Dimension::PMax(val) => {
-            (parent_width.max(parent_height) as f64 * val * 0.01).round() as i64
+        Dimension::Auto => 0.0,
+        Dimension::Pixel(val) => *val as f64,
+        Dimension::Percent(val) => {
+            (if horizontal {
+                parent_width
+            } else {
+                parent_height
+            }) * val
+                * 0.01
         }
-        Dimension::VW(val) => (renderer_width as f64 * val * 0.01).round() as i64,
-        Dimension::VH(val) => (renderer_height as f64 * val * 0.01).round() as i64,
-        Dimension::VMin(val) => {
-            (renderer_width.min(renderer_height) as f64 * val * 0.01).round() as i64
-        }
-        Dimension::VMax(val) => {
-            (renderer_width.max(renderer_height) as f64 * val * 0.01).round() as i64
+        Dimension::PW(val) => parent_width * val * 0.01,
+        Dimension::PH(val) => parent_height * val * 0.01,
+        Dimension::PMin(val) => parent_width.min(parent_height) * val * 0.01,
+        Dimension::PMax(val) => parent_width.max(parent_height) * val * 0.01,
+        Dimension::VW(val) => renderer_width * val * 0.01,
+        Dimension::VH(val) => renderer_height * val * 0.01,
+        Dimension::VMin(val) => renderer_width.min(renderer_height) * val * 0.01,
+        Dimension::VMax(val) => renderer_width.max(renderer_height) * val * 0.01,
+        Dimension::Calc(expression) => expression_value(
+            expression,
+            parent_width,
+            parent_height,
+            renderer_width,
+            renderer_height,
+            horizontal,
+        ),
+    }
+}
+
+fn expression_value(
+    expression: &DimensionExpression,
+    parent_width: f64,
+    parent_height: f64,
+    renderer_width: f64,
+    renderer_height: f64,
+    horizontal: bool,
+) -> f64 {
+    let value = |expression: &DimensionExpression| {
+        expression_value(
+            expression,
+            parent_width,
+            parent_height,
+            renderer_width,
+            renderer_height,
+            horizontal,
+        )
+    };
+    match expression {
+        DimensionExpression::Value(dim) => dimension_value(
+            dim,
+            parent_width,
+            parent_height,
+            renderer_width,
+            renderer_height,
+            horizontal,
+        ),
+        DimensionExpression::Number(val) => *val,
+        DimensionExpression::Add(a, b) => value(a) + value(b),
+        DimensionExpression::Sub(a, b) => value(a) - value(b),
+        DimensionExpression::Mul(a, b) => value(a) * value(b),
+        DimensionExpression::Div(a, b) => {
+            let divisor = value(b);
+            if divisor == 0.0 {
+                0.0
+            } else {
+                value(a) / divisor
+            }
         }
+        DimensionExpression::Min(list) => list.iter().map(value).fold(f64::INFINITY, f64::min),
+        DimensionExpression::Max(list) => list.iter().map(value).fold(f64::NEG_INFINITY, f64::max),
+        DimensionExpression::Clamp(min, val, max) => value(val).min(value(max)).max(value(min)),
     }
 }
 
@@ -127,7 +195,189 @@ impl BufferedConsole {
         self.buffer.clear();
     }
 
+    pub(crate) fn clear_screen(&mut self) {
+        for c in "\x1b[2J".as_bytes() {
+            self.buffer.push(*c);
+        }
+    }
+
     pub(crate) fn clear(&mut self) {
         self.buffer.clear();
     }
 }
+
+#[cfg(unix)]
+pub(crate) struct TerminalMode {
+    original: libc::termios,
+}
+
+#[cfg(not(unix))]
+pub(crate) struct TerminalMode;
+
+impl TerminalMode {
+    #[cfg(unix)]
+    pub(crate) fn enable_raw_mode() -> Option<TerminalMode> {
+        unsafe {
+            let mut original: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return None;
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            //keep output processing so "\n" still returns the carriage
+            raw.c_oflag |= libc::OPOST;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return None;
+            }
+            Some(TerminalMode { original })
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn enable_raw_mode() -> Option<TerminalMode> {
+        None
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn restore(&self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn restore(&self) {}
+}
+
+///waits up to timeout for stdin to become readable
+#[cfg(unix)]
+pub(crate) fn stdin_ready(timeout: std::time::Duration) -> bool {
+    let mut fd = libc::pollfd {
+        fd: libc::STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    unsafe { libc::poll(&mut fd, 1, timeout.as_millis() as libc::c_int) > 0 }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn stdin_ready(_timeout: std::time::Duration) -> bool {
+    true
+}
+
+pub(crate) fn write_escape(sequence: &str) {
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(sequence.as_bytes()).unwrap();
+    stdout.flush().unwrap();
+}
+
+#[cfg(unix)]
+pub(crate) fn read_stdin(buffer: &mut [u8]) -> usize {
+    let count = unsafe { libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr().cast(), buffer.len()) };
+    count.max(0) as usize
+}
+
+#[cfg(not(unix))]
+pub(crate) fn read_stdin(buffer: &mut [u8]) -> usize {
+    use std::io::Read;
+    io::stdin().read(buffer).unwrap_or(0)
+}
+
+pub(crate) fn get_terminal_size() -> (i64, i64) {
+    match terminal_size() {
+        Some(val) => (val.0 .0 as i64, val.1 .0 as i64),
+        None => (0, 0),
+    }
+}
+
+#[cfg(unix)]
+static RESIZE_GENERATION: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+#[cfg(unix)]
+extern "C" fn handle_resize_signal(_: libc::c_int) {
+    RESIZE_GENERATION.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+}
+
+#[cfg(unix)]
+fn install_resize_handler() {
+    static INSTALL: std::sync::Once = std::sync::Once::new();
+    INSTALL.call_once(|| unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handle_resize_signal as *const () as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGWINCH, &action, std::ptr::null_mut());
+    });
+}
+
+pub(crate) const RESIZE_SETTLE_TIME: Duration = Duration::from_millis(100);
+
+///reports terminal size changes once no new SIGWINCH arrived for the settle time
+pub(crate) struct ResizeWatcher {
+    generation: u64,
+    size: (i64, i64),
+    changed_at: Option<Instant>,
+    initialized: bool,
+    pub(crate) settle_time: Duration,
+}
+
+impl ResizeWatcher {
+    pub(crate) fn new(settle_time: Duration) -> ResizeWatcher {
+        #[cfg(unix)]
+        install_resize_handler();
+        ResizeWatcher {
+            generation: 0,
+            size: (0, 0),
+            changed_at: None,
+            initialized: false,
+            settle_time,
+        }
+    }
+
+    #[cfg(unix)]
+    fn resize_signaled(&mut self) -> bool {
+        let generation = RESIZE_GENERATION.load(std::sync::atomic::Ordering::Relaxed);
+        let signaled = generation != self.generation;
+        self.generation = generation;
+        signaled
+    }
+
+    //without signals the size still has to be polled, the generation then encodes the last size
+    #[cfg(not(unix))]
+    fn resize_signaled(&mut self) -> bool {
+        let (width, height) = get_terminal_size();
+        let generation = (width as u64) << 32 | height as u64;
+        let signaled = generation != self.generation;
+        self.generation = generation;
+        signaled
+    }
+
+    ///returns the current size on the first call, then the new size after each settled resize
+    pub(crate) fn poll(&mut self) -> Option<(i64, i64)> {
+        if !self.initialized {
+            self.initialized = true;
+            self.resize_signaled();
+            self.size = get_terminal_size();
+            return Some(self.size);
+        }
+        if self.resize_signaled() {
+            self.changed_at = Some(Instant::now());
+        }
+        match self.changed_at {
+            Some(changed_at) if changed_at.elapsed() >= self.settle_time => {
+                self.changed_at = None;
+                let size = get_terminal_size();
+                if size == self.size {
+                    return None;
+                }
+                self.size = size;
+                Some(size)
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn settling(&self) -> bool {
+        self.changed_at.is_some()
+    }
+}
@@ -1,6 +1,9 @@
 use std::fmt::Display;
 
-use crate::{color::Color, colors::Colors};
+use crate::{
+    color::{BlendMode, Color, Compositing},
+    colors::Colors,
+};
 
 pub struct Pixels;
 
@@ -31,24 +34,51 @@ impl Pixel {
     }
 
     pub fn with_overlay(&self, overlay: &Pixel) -> Pixel {
+        self.with_overlay_in(overlay, Compositing::Srgb)
+    }
+
+    ///with_overlay, blending colors in the given compositing mode
+    pub fn with_overlay_in(&self, overlay: &Pixel, compositing: Compositing) -> Pixel {
+        self.with_blend(overlay, BlendMode::Normal, compositing)
+    }
+
+    ///with_overlay_in, mixing the colors by the blend mode first
+    pub fn with_blend(
+        &self,
+        overlay: &Pixel,
+        blend_mode: BlendMode,
+        compositing: Compositing,
+    ) -> Pixel {
         if "▀▄▅▆▇█▉▊▋▌▐▙▛▜▟▚▞▓▒".contains(self.value) && overlay.value > ' '
         {
             return Pixel {
                 value: overlay.value,
-                background: self.foreground.with_overlay(overlay.background),
-                foreground: self.foreground.with_overlay(overlay.foreground),
+                background: self
+                    .foreground
+                    .with_blend(overlay.background, blend_mode, compositing),
+                foreground: self
+                    .foreground
+                    .with_blend(overlay.foreground, blend_mode, compositing),
             };
         } else if overlay.value > ' ' {
             return Pixel {
                 value: overlay.value,
-                background: self.background.with_overlay(overlay.background),
-                foreground: self.background.with_overlay(overlay.foreground),
+                background: self
+                    .background
+                    .with_blend(overlay.background, blend_mode, compositing),
+                foreground: self
+                    .background
+                    .with_blend(overlay.foreground, blend_mode, compositing),
             };
         } else if overlay.value == ' ' {
             return Pixel {
                 value: self.value,
-                background: self.background.with_overlay(overlay.background),
-                foreground: self.foreground.with_overlay(overlay.background),
+                background: self
+                    .background
+                    .with_blend(overlay.background, blend_mode, compositing),
+                foreground: self
+                    .foreground
+                    .with_blend(overlay.background, blend_mode, compositing),
             };
         } else {
             return (*self).clone();
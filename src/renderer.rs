@@ -2,32 +2,39 @@ use std::{
     io::{self, Write},
     sync::{
         atomic::{AtomicBool, Ordering},
-        Arc,
+        Arc, RwLock,
     },
     thread::{self, JoinHandle},
     time::{Duration, Instant},
     vec,
 };
 
-use terminal_size::terminal_size;
-
 use crate::{
+    color::Compositing,
     colors::Colors,
-    misc::{generic_dimension_calc, BufferedConsole},
-    pixel::Pixel,
+    misc::{generic_dimension_calc, BufferedConsole, ResizeWatcher, RESIZE_SETTLE_TIME},
+    pixel::{Pixel, Pixels},
     renderer_object_style::{AlignmentX, AlignmentY, RendererObjectStyle},
+    renderer_object_value::RendererObjectValue,
     renderer_object_wrapper::RendererObject,
 };
 
 pub struct Renderer {
     previous_buffer: Vec<Vec<Pixel>>,
+    //the screen as composed this frame, kept between frames so it is only allocated on resize
+    pub(crate) frame: Vec<Vec<Pixel>>,
     width: i64,
     height: i64,
     padding: i64,
+    cell_aspect: f64,
+    compositing: Compositing,
     object: Option<RendererObject>,
     console: BufferedConsole,
     drawing: bool,
     lock: Arc<AtomicBool>,
+    //width and height for hit tests from other threads while running
+    screen_size: Arc<RwLock<(i64, i64)>>,
+    resize_watcher: ResizeWatcher,
 
     pub disable_output: bool,
 }
@@ -36,13 +43,18 @@ impl Renderer {
     pub fn new() -> Renderer {
         Renderer {
             previous_buffer: Vec::new(),
+            frame: Vec::new(),
             width: 0,
             height: 0,
             object: None,
             padding: 5,
+            cell_aspect: 2.0,
+            compositing: Compositing::Srgb,
             console: BufferedConsole::new(),
             drawing: false,
             lock: Arc::new(AtomicBool::new(false)),
+            screen_size: Arc::new(RwLock::new((0, 0))),
+            resize_watcher: ResizeWatcher::new(RESIZE_SETTLE_TIME),
             disable_output: false,
         }
     }
@@ -75,23 +87,150 @@ impl Renderer {
         self.padding = buffer;
     }
 
-    pub fn draw(&mut self, force_update: bool) {
+    ///how many times taller than wide a terminal cell is, used by aspect ratios,
+    ///Input::cell_aspect reports the terminal's value after a query
+    pub fn set_cell_aspect(&mut self, cell_aspect: f64) {
+        self.cell_aspect = cell_aspect;
+    }
+
+    ///how translucent objects and colors blend, linear light keeps overlays from darkening
+    pub fn set_compositing(&mut self, compositing: Compositing) {
+        self.compositing = compositing;
+    }
+
+    ///how long the terminal size has to stay unchanged before redrawing after a resize
+    pub fn set_resize_settle_time(&mut self, settle_time: Duration) {
+        self.resize_watcher.settle_time = settle_time;
+    }
+
+    ///returns the deepest object drawn at the given terminal cell
+    pub fn hit_test(&self, x: i64, y: i64) -> Option<RendererObject> {
+        hit_test(self.object.as_ref()?, self.width, self.height, x, y)
+    }
+
+    ///draws the object into the frame at its place on screen,
+    ///returns the cells it covers as (start_x, start_y, end_x, end_y)
+    fn composite(
+        &self,
+        object: &mut RendererObjectValue,
+        frame: &mut [Vec<Pixel>],
+        overlay: bool,
+    ) -> (i64, i64, i64, i64) {
+        let (object_x, object_y) = object_position(object, self.width, self.height);
+        let (object_width, object_height) = (object.calculated_width, object.calculated_height);
+        let (alignment_offset_x, alignment_offset_y) =
+            alignment_offset(object, self.width, self.height);
+
+        let start_x: i64 = (alignment_offset_x + object_x).max(0).min(self.width);
+        let end_x: i64 = (alignment_offset_x + object_x + object_width)
+            .max(0)
+            .min(self.width);
+        let start_y: i64 = (alignment_offset_y + object_y).max(0).min(self.height);
+        let end_y: i64 = (alignment_offset_y + object_y + object_height)
+            .max(0)
+            .min(self.height);
+        if !object.is_shown() {
+            return (start_x, start_y, end_x, end_y);
+        }
+
+        let (buffer_x, buffer_y) = if overlay {
+            (alignment_offset_x + object_x, alignment_offset_y + object_y)
+        } else {
+            (alignment_offset_x, alignment_offset_y)
+        };
+        let blend_mode = object.style.blend_mode;
+        object.get_buffer(buffer_x, buffer_y, self.padding);
+        let (origin_x, origin_y) = if overlay {
+            (
+                buffer_x + object.buffer_origin.0,
+                buffer_y + object.buffer_origin.1,
+            )
+        } else {
+            (start_x, start_y)
+        };
+        let current_buffer = &object.buffer;
+
+        for i in start_y..end_y {
+            for j in start_x..end_x {
+                let Some(pixel) = current_buffer
+                    .get((i - origin_y) as usize)
+                    .and_then(|line| line.get((j - origin_x) as usize))
+                else {
+                    continue;
+                };
+                let cell = &mut frame[i as usize][j as usize];
+                *cell = if overlay {
+                    cell.with_blend(pixel, blend_mode, self.compositing)
+                } else {
+                    *pixel
+                };
+            }
+        }
+        (start_x, start_y, end_x, end_y)
+    }
+
+    //sets the screen size, the next draw repaints every cell
+    pub(crate) fn resize(&mut self, width: i64, height: i64) {
+        self.width = width;
+        self.height = height;
+        *self.screen_size.write().unwrap() = (width, height);
+        self.previous_buffer = Vec::new();
+        self.frame = vec::from_elem(
+            vec::from_elem(Pixels::EMPTY, width as usize),
+            height as usize,
+        );
+    }
+
+    //lays out the object and draws it with its fixed descendants into the frame,
+    //returns the cells the object covers on screen
+    pub(crate) fn render_frame(
+        &mut self,
+        object: &mut RendererObjectValue,
+    ) -> (i64, i64, i64, i64) {
+        object.update_value();
+        object.set_cell_aspect(self.cell_aspect);
+        object.set_compositing(self.compositing);
+        object.process_geometry(
+            self.width,
+            self.height,
+            self.width,
+            self.height,
+            self.padding,
+        );
+
+        let mut frame = std::mem::take(&mut self.frame);
+        for line in &mut frame {
+            line.fill(Pixels::EMPTY);
+        }
+        let rect = self.composite(object, &mut frame, false);
+        //fixed objects break out of their parents and are drawn above the tree
+        for fixed_cell in object.fixed_descendants() {
+            self.composite(&mut fixed_cell.write().unwrap(), &mut frame, true);
+        }
+        self.frame = frame;
+        rect
+    }
+
+    pub fn draw(&mut self, mut force_update: bool) {
         if self.drawing {
             return;
         }
         if self.lock.load(Ordering::Relaxed) {
             return;
         }
+        if let Some((terminal_width, terminal_height)) = self.resize_watcher.poll() {
+            self.resize(terminal_width, terminal_height);
+            self.console.clear_screen();
+            force_update = true;
+        }
+        //the terminal is still being resized, drawing now would only get reflowed
+        if self.resize_watcher.settling() {
+            return;
+        }
         self.drawing = true;
-        if let Some(object_wrapper) = &mut self.object {
+        if let Some(object_wrapper) = self.object.clone() {
             {
-                let (terminal_width, terminal_height) = match terminal_size() {
-                    Some(val) => (val.0 .0 as i64, val.1 .0 as i64),
-                    None => (0, 0),
-                };
-                if self.width != terminal_width || self.height != terminal_height {
-                    self.width = terminal_width;
-                    self.height = terminal_height;
+                if self.previous_buffer.len() != self.height as usize {
                     self.previous_buffer = vec::from_elem(
                         vec::from_elem(
                             Pixel {
@@ -105,86 +244,26 @@ impl Renderer {
                     );
                 }
                 let mut object = object_wrapper.value.write().unwrap();
-
-                {
-                    object.update_value();
-                }
-
-                object.process_geometry(
-                    self.width,
-                    self.height,
-                    self.width,
-                    self.height,
-                    self.padding,
-                );
-
-                let object_x: i64 = generic_dimension_calc(
-                    &object.x,
-                    self.width,
-                    self.height,
-                    self.width,
-                    self.height,
-                    true,
-                );
-                let object_y: i64 = generic_dimension_calc(
-                    &object.y,
-                    self.width,
-                    self.height,
-                    self.width,
-                    self.height,
-                    false,
-                );
-                let (object_width, object_height) =
-                    (object.calculated_width, object.calculated_height);
-
-                let style: &RendererObjectStyle = &object.style;
-                let alignment_offset_x: i64 = if let Some(style) = style.external_alignment_x {
-                    match style {
-                        AlignmentX::Left => 0,
-                        AlignmentX::Center => self.width / 2 - object_width / 2,
-                        AlignmentX::Right => self.width - object_width,
-                    }
-                } else {
-                    0
-                };
-                let alignment_offset_y: i64 = if let Some(style) = style.external_alignment_y {
-                    match style {
-                        AlignmentY::Top => 0,
-                        AlignmentY::Center => self.height / 2 - object_height / 2,
-                        AlignmentY::Bottom => self.height - object_height,
-                    }
-                } else {
-                    0
-                };
-                let current_buffer: &Vec<Vec<Pixel>> =
-                    object.get_buffer(alignment_offset_x, alignment_offset_y, self.padding);
-
-                let start_x: i64 = (alignment_offset_x + object_x).max(0).min(self.width);
-                let end_x: i64 = (alignment_offset_x + object_x + object_width as i64)
-                    .max(0)
-                    .min(self.width);
-                let start_y: i64 = (alignment_offset_y + object_y).max(0).min(self.height);
-                let end_y: i64 = (alignment_offset_y + object_y + object_height as i64)
-                    .max(0)
-                    .min(self.height);
+                let (start_x, start_y, end_x, end_y) = self.render_frame(&mut object);
+                let frame = &self.frame;
 
                 let mut last_i: i64 = -1;
-                for i in start_y..end_y {
+                for i in 0..self.height {
                     let mut last_j: i64 = -1;
-                    for j in start_x..end_x {
-                        let padding: i64 = (self.width - terminal_width).max(0).min(self.padding);
-                        let current_pixel: Pixel = current_buffer[(i - start_y + padding) as usize]
-                            [(j - start_x + padding) as usize]
-                            .clone();
-                        if self.previous_buffer[i as usize][j as usize] != current_pixel
-                            || force_update
-                        {
+                    for j in 0..self.width {
+                        let current_pixel: Pixel = frame[i as usize][j as usize];
+                        //a forced draw repaints the whole object but leaves blank cells around it alone
+                        let forced = force_update
+                            && (current_pixel != Pixels::EMPTY
+                                || ((start_x..end_x).contains(&j)
+                                    && (start_y..end_y).contains(&i)));
+                        if self.previous_buffer[i as usize][j as usize] != current_pixel || forced {
                             if j != last_j + 1 || i != last_i {
                                 self.console.set_cursor_position(j, i);
                                 last_i = i;
                                 last_j = j;
                             }
-                            self.previous_buffer[i as usize][j as usize] = current_pixel.clone();
+                            self.previous_buffer[i as usize][j as usize] = current_pixel;
                             self.console.print(current_pixel);
                         }
                     }
@@ -207,6 +286,8 @@ impl Renderer {
             thread: None,
             running: running.clone(),
             lock: self.lock.clone(),
+            object: self.object.clone(),
+            screen_size: self.screen_size.clone(),
         };
         running_renderer.thread = Some(thread::spawn(move || {
             let mut renderer = self;
@@ -302,10 +383,74 @@ impl Renderer {
     }
 }
 
+fn object_position(object: &RendererObjectValue, width: i64, height: i64) -> (i64, i64) {
+    (
+        generic_dimension_calc(&object.x, width, height, width, height, true),
+        generic_dimension_calc(&object.y, width, height, width, height, false),
+    )
+}
+
+fn alignment_offset(object: &RendererObjectValue, width: i64, height: i64) -> (i64, i64) {
+    let style: &RendererObjectStyle = &object.style;
+    let anchored_x = object.anchored_position(width, height, true);
+    let anchored_y = object.anchored_position(width, height, false);
+    let alignment_offset_x: i64 = if let Some(anchored_x) = anchored_x {
+        anchored_x
+    } else if let Some(style) = style.external_alignment_x {
+        match style {
+            AlignmentX::Left => 0,
+            AlignmentX::Center => width / 2 - object.calculated_width / 2,
+            AlignmentX::Right => width - object.calculated_width,
+        }
+    } else {
+        0
+    };
+    let alignment_offset_y: i64 = if let Some(anchored_y) = anchored_y {
+        anchored_y
+    } else if let Some(style) = style.external_alignment_y {
+        match style {
+            AlignmentY::Top => 0,
+            AlignmentY::Center => height / 2 - object.calculated_height / 2,
+            AlignmentY::Bottom => height - object.calculated_height,
+        }
+    } else {
+        0
+    };
+    (alignment_offset_x, alignment_offset_y)
+}
+
+//the screen cell an object is drawn at, without clamping to the screen
+fn screen_position(object: &RendererObjectValue, width: i64, height: i64) -> (i64, i64) {
+    let (alignment_offset_x, alignment_offset_y) = alignment_offset(object, width, height);
+    let (object_x, object_y) = object_position(object, width, height);
+    (alignment_offset_x + object_x, alignment_offset_y + object_y)
+}
+
+fn hit_test(
+    object_wrapper: &RendererObject,
+    width: i64,
+    height: i64,
+    x: i64,
+    y: i64,
+) -> Option<RendererObject> {
+    let fixed_objects = object_wrapper.value.read().unwrap().fixed_descendants();
+    let mut objects: Vec<_> = fixed_objects.into_iter().rev().collect();
+    objects.push(object_wrapper.value.clone());
+    objects
+        .iter()
+        .find_map(|object| {
+            let (object_x, object_y) = screen_position(&object.read().unwrap(), width, height);
+            RendererObjectValue::hit_test(object, x - object_x, y - object_y)
+        })
+        .map(RendererObject::from_value)
+}
+
 pub struct RunningRenderer {
     thread: Option<JoinHandle<Renderer>>,
     running: Arc<AtomicBool>,
     lock: Arc<AtomicBool>,
+    object: Option<RendererObject>,
+    screen_size: Arc<RwLock<(i64, i64)>>,
 }
 
 impl RunningRenderer {
@@ -317,6 +462,12 @@ impl RunningRenderer {
         self.lock.store(false, Ordering::Relaxed);
     }
 
+    ///same as Renderer::hit_test, against the last size the renderer drew at
+    pub fn hit_test(&self, x: i64, y: i64) -> Option<RendererObject> {
+        let (width, height) = *self.screen_size.read().unwrap();
+        hit_test(self.object.as_ref()?, width, height, x, y)
+    }
+
     pub fn stop(self) -> Renderer {
         self.running.store(false, Ordering::Relaxed);
         self.thread.unwrap().join().unwrap()
@@ -257,4 +257,14 @@ impl Border {
         self.left.foreground = color;
         self
     }
+
+    ///cells taken by the border on each side, as (top, right, bottom, left)
+    pub(crate) fn thickness(&self) -> (i64, i64, i64, i64) {
+        (
+            (self.top.value != '\0') as i64,
+            (self.right.value != '\0') as i64,
+            (self.bottom.value != '\0') as i64,
+            (self.left.value != '\0') as i64,
+        )
+    }
 }
@@ -1,4 +1,9 @@
-use crate::renderer_object_border::{Border, Borders};
+use crate::{
+    color::{BlendMode, Color},
+    geometry::{Dimension, DimensionParseError},
+    misc::generic_dimension_calc,
+    renderer_object_border::{Border, Borders},
+};
 
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub enum AlignmentX {
@@ -14,23 +19,330 @@ pub enum AlignmentY {
     Bottom,
 }
 
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Overflow {
+    //children are cut off at the object's bounds
+    Clip,
+    //children may paint over the parent's area
+    Visible,
+    //like clip, with the content moved by the object's scroll offset
+    Scroll,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Position {
+    //placed inside the parent
+    Relative,
+    //placed relative to the renderer, drawn above everything that isn't fixed
+    Fixed,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TextWrap {
+    None,
+    //breaks lines at the content width
+    Char,
+    //breaks lines at whitespace, words longer than a line are split
+    Word,
+}
+
+//alignment of each text line, overrides internal_alignment_x for text
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+    //spaces are widened to fill wrapped lines, the last line of a paragraph stays left aligned
+    Justify,
+}
+
+//where lines wider than the content are cut and marked with '…'
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Ellipsis {
+    Start,
+    Middle,
+    End,
+}
+
+//drawn over the last column and row of scrolled objects whose content doesn't fit
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Scrollbar {
+    pub thumb_color: Color,
+    pub track_color: Color,
+}
+
+impl Scrollbar {
+    pub fn new(thumb_color: Color, track_color: Color) -> Scrollbar {
+        Scrollbar {
+            thumb_color,
+            track_color,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum FlexDirection {
+    Row,
+    Column,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum JustifyContent {
+    Start,
+    Center,
+    End,
+    SpaceBetween,
+    SpaceAround,
+    SpaceEvenly,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum AlignItems {
+    Start,
+    Center,
+    End,
+    Stretch,
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct FlexLayout {
+    pub direction: FlexDirection,
+    pub gap: Dimension,
+    pub justify_content: JustifyContent,
+    pub align_items: AlignItems,
+    pub wrap: bool,
+}
+
+impl FlexLayout {
+    pub fn new(direction: FlexDirection) -> FlexLayout {
+        FlexLayout {
+            direction,
+            gap: Dimension::Pixel(0),
+            justify_content: JustifyContent::Start,
+            align_items: AlignItems::Stretch,
+            wrap: false,
+        }
+    }
+
+    pub fn row() -> FlexLayout {
+        FlexLayout::new(FlexDirection::Row)
+    }
+
+    pub fn column() -> FlexLayout {
+        FlexLayout::new(FlexDirection::Column)
+    }
+
+    pub fn set_gap(&mut self, gap: Dimension) -> &mut Self {
+        self.gap = gap;
+        self
+    }
+
+    pub fn set_justify_content(&mut self, justify_content: JustifyContent) -> &mut Self {
+        self.justify_content = justify_content;
+        self
+    }
+
+    pub fn set_align_items(&mut self, align_items: AlignItems) -> &mut Self {
+        self.align_items = align_items;
+        self
+    }
+
+    pub fn set_wrap(&mut self, wrap: bool) -> &mut Self {
+        self.wrap = wrap;
+        self
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum GridTrack {
+    //Dimension::Auto fits the track to its content
+    Size(Dimension),
+    //share of the space left by the other tracks
+    Fr(f64),
+}
+
+impl GridTrack {
+    pub fn from_html(html: &str) -> Result<GridTrack, DimensionParseError> {
+        let lowercase_html = html.trim().to_ascii_lowercase();
+        match lowercase_html.strip_suffix("fr") {
+            Some(val) => match val.trim().parse::<f64>() {
+                //like css, negative fractions are invalid, inf and nan would break the layout
+                Ok(val) if val.is_finite() && val >= 0.0 => Ok(GridTrack::Fr(val)),
+                _ => {
+                    let start = html.len() - html.trim_start().len();
+                    Err(DimensionParseError::InvalidNumber {
+                        input: html.to_string(),
+                        span: start + val.len() - val.trim_start().len()
+                            ..start + val.trim_end().len(),
+                        expected: "a non negative number before fr",
+                    })
+                }
+            },
+            None => Dimension::from_html(html).map(GridTrack::Size),
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct GridLayout {
+    pub columns: Vec<GridTrack>,
+    pub rows: Vec<GridTrack>,
+    pub column_gap: Dimension,
+    pub row_gap: Dimension,
+}
+
+impl GridLayout {
+    ///children placed outside the given rows get extra rows sized to their content
+    pub fn new(columns: Vec<GridTrack>, rows: Vec<GridTrack>) -> GridLayout {
+        GridLayout {
+            columns,
+            rows,
+            column_gap: Dimension::Pixel(0),
+            row_gap: Dimension::Pixel(0),
+        }
+    }
+
+    pub fn set_gap(&mut self, column_gap: Dimension, row_gap: Dimension) -> &mut Self {
+        (self.column_gap, self.row_gap) = (column_gap, row_gap);
+        self
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct GridPlacement {
+    pub row: usize,
+    pub column: usize,
+    pub row_span: usize,
+    pub column_span: usize,
+}
+
+impl GridPlacement {
+    pub fn new(row: usize, column: usize) -> GridPlacement {
+        GridPlacement {
+            row,
+            column,
+            row_span: 1,
+            column_span: 1,
+        }
+    }
+
+    pub fn set_span(&mut self, row_span: usize, column_span: usize) -> &mut Self {
+        (self.row_span, self.column_span) = (row_span.max(1), column_span.max(1));
+        self
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum Layout {
+    //children are placed by their own position and external alignment
+    None,
+    Flex(FlexLayout),
+    Grid(GridLayout),
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Spacing {
+    pub top: Dimension,
+    pub right: Dimension,
+    pub bottom: Dimension,
+    pub left: Dimension,
+}
+
+impl Spacing {
+    pub fn new(top: Dimension, right: Dimension, bottom: Dimension, left: Dimension) -> Spacing {
+        Spacing {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    pub fn all(value: Dimension) -> Spacing {
+        Spacing::new(value, value, value, value)
+    }
+
+    pub fn symmetric(vertical: Dimension, horizontal: Dimension) -> Spacing {
+        Spacing::new(vertical, horizontal, vertical, horizontal)
+    }
+
+    ///cells on each side, as (top, right, bottom, left)
+    pub(crate) fn resolve(
+        &self,
+        parent_width: i64,
+        parent_height: i64,
+        renderer_width: i64,
+        renderer_height: i64,
+    ) -> (i64, i64, i64, i64) {
+        let side = |dimension: &Dimension, horizontal: bool| {
+            generic_dimension_calc(
+                dimension,
+                parent_width,
+                parent_height,
+                renderer_width,
+                renderer_height,
+                horizontal,
+            )
+        };
+        (
+            side(&self.top, false),
+            side(&self.right, true),
+            side(&self.bottom, false),
+            side(&self.left, true),
+        )
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct RendererObjectStyle {
     pub border: Border,
+    //inside the border, taken from the object's size
+    pub padding: Spacing,
+    //outside the border, taken from the parent's space
+    pub margin: Spacing,
+    pub overflow: Overflow,
+    pub scrollbar: Option<Scrollbar>,
+    pub text_wrap: TextWrap,
+    pub ellipsis: Option<Ellipsis>,
+    pub text_align: Option<TextAlign>,
+    pub tab_width: usize,
     pub internal_alignment_x: AlignmentX,
     pub internal_alignment_y: AlignmentY,
     pub external_alignment_x: Option<AlignmentX>,
     pub external_alignment_y: Option<AlignmentY>,
+    pub layout: Layout,
+    pub flex_grow: f64,
+    pub flex_shrink: f64,
+    pub grid_placement: Option<GridPlacement>,
+    pub position: Position,
+    pub z_index: i64,
+    //how the object's pixels mix with what its parent drew below them
+    pub blend_mode: BlendMode,
 }
 
 impl RendererObjectStyle {
     pub fn new() -> RendererObjectStyle {
         RendererObjectStyle {
             border: Borders::EMPTY,
+            padding: Spacing::all(Dimension::Pixel(0)),
+            margin: Spacing::all(Dimension::Pixel(0)),
+            overflow: Overflow::Clip,
+            scrollbar: None,
+            text_wrap: TextWrap::None,
+            ellipsis: None,
+            text_align: None,
+            tab_width: 8,
             internal_alignment_x: AlignmentX::Left,
             internal_alignment_y: AlignmentY::Top,
             external_alignment_x: None,
             external_alignment_y: None,
+            layout: Layout::None,
+            flex_grow: 0.0,
+            flex_shrink: 1.0,
+            grid_placement: None,
+            position: Position::Relative,
+            z_index: 0,
+            blend_mode: BlendMode::Normal,
         }
     }
 
@@ -52,4 +364,82 @@ impl RendererObjectStyle {
         self.border = border;
         self
     }
+
+    pub fn set_padding(&mut self, padding: Spacing) -> &mut Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn set_margin(&mut self, margin: Spacing) -> &mut Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) -> &mut Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn set_scrollbar(&mut self, scrollbar: Option<Scrollbar>) -> &mut Self {
+        self.scrollbar = scrollbar;
+        self
+    }
+
+    ///how text set with set_text is broken into lines
+    pub fn set_text_wrap(&mut self, text_wrap: TextWrap) -> &mut Self {
+        self.text_wrap = text_wrap;
+        self
+    }
+
+    pub fn set_ellipsis(&mut self, ellipsis: Option<Ellipsis>) -> &mut Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+
+    ///None aligns text lines by internal_alignment_x
+    pub fn set_text_align(&mut self, text_align: Option<TextAlign>) -> &mut Self {
+        self.text_align = text_align;
+        self
+    }
+
+    ///distance between tab stops, tabs are removed when it is 0
+    pub fn set_tab_width(&mut self, tab_width: usize) -> &mut Self {
+        self.tab_width = tab_width;
+        self
+    }
+
+    ///how the object places its children
+    pub fn set_layout(&mut self, layout: Layout) -> &mut Self {
+        self.layout = layout;
+        self
+    }
+
+    ///share of the free space taken or given up when the parent uses a flex layout
+    pub fn set_flex(&mut self, grow: f64, shrink: f64) -> &mut Self {
+        (self.flex_grow, self.flex_shrink) = (grow, shrink);
+        self
+    }
+
+    ///fixed objects take no space in their parent and ignore its clipping
+    pub fn set_position(&mut self, position: Position) -> &mut Self {
+        self.position = position;
+        self
+    }
+
+    ///siblings with a higher z index are drawn on top, equal ones keep their order
+    pub fn set_z_index(&mut self, z_index: i64) -> &mut Self {
+        self.z_index = z_index;
+        self
+    }
+
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) -> &mut Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    ///cell used when the parent uses a grid layout, None takes the next free cell
+    pub fn set_grid_placement(&mut self, grid_placement: Option<GridPlacement>) -> &mut Self {
+        self.grid_placement = grid_placement;
+        self
+    }
 }
@@ -4,12 +4,20 @@ use std::{
 };
 
 use crate::{
-    color::Color,
+    color::{Color, Compositing},
     color_area::{ColorArea, ColorLayer},
+    colors::Colors,
+    focus::KeyHandler,
     geometry::Dimension,
+    layout::{self, LayoutItem, Track},
     misc::generic_dimension_calc,
     pixel::Pixel,
-    renderer_object_style::{AlignmentX, AlignmentY, RendererObjectStyle},
+    renderer_object_border::Border,
+    renderer_object_style::{
+        AlignmentX, AlignmentY, FlexDirection, GridLayout, GridPlacement, GridTrack, Layout,
+        Overflow, Position, RendererObjectStyle, TextAlign, TextWrap,
+    },
+    text,
 };
 
 #[derive(Clone)]
@@ -48,9 +56,27 @@ pub(crate) struct RendererObjectValue {
     pub(crate) y: Dimension,
     pub(crate) width: Dimension,
     pub(crate) height: Dimension,
+    pub(crate) min_width: Dimension,
+    pub(crate) max_width: Dimension,
+    pub(crate) min_height: Dimension,
+    pub(crate) max_height: Dimension,
+
+    //distances from the parent's edges, auto leaves the axis to x, y and the alignment
+    pub(crate) left: Dimension,
+    pub(crate) right: Dimension,
+    pub(crate) top: Dimension,
+    pub(crate) bottom: Dimension,
+
+    //the requested scroll, kept as is so content added later can still be scrolled to
+    pub(crate) scroll_x: i64,
+    pub(crate) scroll_y: i64,
+    //the requested scroll clamped to the content, set by process_geometry
+    pub(crate) scroll_position: (i64, i64),
 
     pub(crate) absolute_x: i64,
     pub(crate) absolute_y: i64,
+    //where the buffer starts inside the object, it only holds the part that's on screen
+    pub(crate) buffer_origin: (i64, i64),
 
     pub(crate) parent_width: i64,
     pub(crate) parent_height: i64,
@@ -61,6 +87,20 @@ pub(crate) struct RendererObjectValue {
     pub(crate) calculated_width: i64,
     pub(crate) calculated_height: i64,
 
+    //size and position imposed by the parent's layout
+    pub(crate) layout_width: Option<i64>,
+    pub(crate) layout_height: Option<i64>,
+    pub(crate) layout_position: Option<(i64, i64)>,
+
+    //hidden objects keep their space, undisplayed ones take none, neither is drawn
+    pub(crate) visible: bool,
+    pub(crate) displayed: bool,
+
+    //visual width divided by height, cells are cell_aspect times taller than wide
+    pub(crate) aspect_ratio: Option<f64>,
+    pub(crate) cell_aspect: f64,
+    pub(crate) compositing: Compositing,
+
     pub(crate) update_size: bool,
     pub(crate) update_content: bool,
     pub(crate) update_value_signal: Arc<RwLock<UpdateValueSignaler>>,
@@ -92,6 +132,12 @@ pub(crate) struct RendererObjectValue {
     pub(crate) style: RendererObjectStyle,
     pub(crate) changed_style: bool,
 
+    pub(crate) focusable: bool,
+    pub(crate) focused: bool,
+    pub(crate) tab_index: Option<i64>,
+    pub(crate) focused_style: Option<RendererObjectStyle>,
+    pub(crate) key_handler: Option<KeyHandler>,
+
     pub(crate) parent_location: usize,
 
     pub(crate) new_self: Option<Arc<RwLock<RendererObjectValue>>>,
@@ -125,11 +171,64 @@ impl RendererObjectValue {
 
             if self.width != new_self.width {
                 self.width = new_self.width;
+                self.update_size = true;
                 update = true;
             }
 
             if self.height != new_self.height {
                 self.height = new_self.height;
+                self.update_size = true;
+                update = true;
+            }
+
+            if (
+                &self.min_width,
+                &self.max_width,
+                &self.min_height,
+                &self.max_height,
+            ) != (
+                &new_self.min_width,
+                &new_self.max_width,
+                &new_self.min_height,
+                &new_self.max_height,
+            ) {
+                self.min_width = new_self.min_width;
+                self.max_width = new_self.max_width;
+                self.min_height = new_self.min_height;
+                self.max_height = new_self.max_height;
+                self.update_size = true;
+                update = true;
+            }
+
+            if (&self.left, &self.right, &self.top, &self.bottom)
+                != (
+                    &new_self.left,
+                    &new_self.right,
+                    &new_self.top,
+                    &new_self.bottom,
+                )
+            {
+                self.left = new_self.left;
+                self.right = new_self.right;
+                self.top = new_self.top;
+                self.bottom = new_self.bottom;
+                self.update_size = true;
+                update_parent = true;
+            }
+
+            if (self.visible, self.displayed) != (new_self.visible, new_self.displayed) {
+                (self.visible, self.displayed) = (new_self.visible, new_self.displayed);
+                update_parent = true;
+            }
+
+            if self.aspect_ratio != new_self.aspect_ratio {
+                self.aspect_ratio = new_self.aspect_ratio;
+                self.update_size = true;
+                update = true;
+            }
+
+            if (self.scroll_x, self.scroll_y) != (new_self.scroll_x, new_self.scroll_y) {
+                (self.scroll_x, self.scroll_y) = (new_self.scroll_x, new_self.scroll_y);
                 update = true;
             }
 
@@ -183,7 +282,10 @@ impl RendererObjectValue {
             }
 
             if new_self.changed_style {
-                self.style = new_self.style.clone();
+                self.style = match (&new_self.focused_style, new_self.focused) {
+                    (Some(focused_style), true) => focused_style.clone(),
+                    _ => new_self.style.clone(),
+                };
                 new_self.changed_style = false;
                 update = true;
             }
@@ -221,8 +323,454 @@ impl RendererObjectValue {
         renderer_width: i64,
         renderer_height: i64,
     ) -> (i64, i64) {
+        let (width, height) =
+            self.natural_geometry(parent_width, parent_height, renderer_width, renderer_height);
+        let anchored_size = |horizontal: bool| {
+            self.anchored_size(
+                parent_width,
+                parent_height,
+                renderer_width,
+                renderer_height,
+                horizontal,
+            )
+        };
+        let forced_width = self.layout_width.or_else(|| anchored_size(true));
+        let forced_height = self.layout_height.or_else(|| anchored_size(false));
+        //the aspect ratio follows the axis the parent decided on
+        let (width, height) = match (forced_width, forced_height) {
+            (Some(width), None) => self.keep_aspect_ratio((width, height), true),
+            (None, Some(height)) => self.keep_aspect_ratio((width, height), false),
+            (width_override, height_override) => (
+                width_override.unwrap_or(width),
+                height_override.unwrap_or(height),
+            ),
+        };
+        self.constrain_size(
+            (width, height),
+            parent_width,
+            parent_height,
+            renderer_width,
+            renderer_height,
+            false,
+        )
+    }
+
+    ///derives the height from the width or the other way around
+    fn keep_aspect_ratio(&self, (width, height): (i64, i64), from_width: bool) -> (i64, i64) {
+        let factor = match self.aspect_ratio {
+            Some(aspect_ratio) if aspect_ratio > 0.0 => aspect_ratio * self.cell_aspect,
+            _ => return (width, height),
+        };
+        if from_width {
+            (width, (width as f64 / factor).round() as i64)
+        } else {
+            ((height as f64 * factor).round() as i64, height)
+        }
+    }
+
+    pub(crate) fn set_cell_aspect(&mut self, cell_aspect: f64) {
+        if self.cell_aspect != cell_aspect {
+            self.cell_aspect = cell_aspect;
+            self.update_content = true;
+            if self.aspect_ratio.is_some() {
+                self.update_size = true;
+            }
+        }
+    }
+
+    pub(crate) fn set_compositing(&mut self, compositing: Compositing) {
+        if self.compositing != compositing {
+            self.compositing = compositing;
+            self.update_content = true;
+        }
+    }
+
+    ///applies the min and max sizes, the min wins when they conflict,
+    ///intrinsic sizes ignore the limits relative to the parent
+    fn constrain_size(
+        &self,
+        (width, height): (i64, i64),
+        parent_width: i64,
+        parent_height: i64,
+        renderer_width: i64,
+        renderer_height: i64,
+        intrinsic: bool,
+    ) -> (i64, i64) {
+        let limit = |dimension: &Dimension, horizontal: bool| match dimension {
+            Dimension::Auto => None,
+            _ if intrinsic && dimension.is_parent_relative() => None,
+            _ => Some(generic_dimension_calc(
+                dimension,
+                parent_width,
+                parent_height,
+                renderer_width,
+                renderer_height,
+                horizontal,
+            )),
+        };
+        let constrain = |size: i64, min: &Dimension, max: &Dimension, horizontal: bool| {
+            let size = limit(max, horizontal).map_or(size, |max| size.min(max));
+            limit(min, horizontal).map_or(size, |min| size.max(min))
+        };
+        (
+            constrain(width, &self.min_width, &self.max_width, true),
+            constrain(height, &self.min_height, &self.max_height, false),
+        )
+    }
+
+    ///true if any of the width or height constraints depend on the given size
+    fn size_depends_on(&self, horizontal: bool, relative: fn(&Dimension) -> bool) -> bool {
+        let padding = &self.style.padding;
+        let (start, end) = self.anchors(horizontal);
+        //a size stretched between anchors acts like calc(100% - start - end)
+        let stretched = self.stretched(horizontal);
+        if stretched && relative(&Dimension::Percent(100.0)) {
+            return true;
+        }
+        if horizontal {
+            [
+                &self.width,
+                &self.min_width,
+                &self.max_width,
+                &padding.left,
+                &padding.right,
+            ]
+        } else {
+            [
+                &self.height,
+                &self.min_height,
+                &self.max_height,
+                &padding.top,
+                &padding.bottom,
+            ]
+        }
+        .into_iter()
+        .any(relative)
+            || (stretched && (relative(start) || relative(end)))
+    }
+
+    ///the (left, right) or (top, bottom) anchors
+    fn anchors(&self, horizontal: bool) -> (&Dimension, &Dimension) {
+        if horizontal {
+            (&self.left, &self.right)
+        } else {
+            (&self.top, &self.bottom)
+        }
+    }
+
+    ///true if an auto size fills the space between both anchors
+    fn stretched(&self, horizontal: bool) -> bool {
+        let size = if horizontal {
+            &self.width
+        } else {
+            &self.height
+        };
+        let (start, end) = self.anchors(horizontal);
+        *size == Dimension::Auto && *start != Dimension::Auto && *end != Dimension::Auto
+    }
+
+    fn anchored_size(
+        &self,
+        parent_width: i64,
+        parent_height: i64,
+        renderer_width: i64,
+        renderer_height: i64,
+        horizontal: bool,
+    ) -> Option<i64> {
+        if !self.stretched(horizontal) {
+            return None;
+        }
+        let resolve = |dimension: &Dimension| {
+            generic_dimension_calc(
+                dimension,
+                parent_width,
+                parent_height,
+                renderer_width,
+                renderer_height,
+                horizontal,
+            )
+        };
+        let (start, end) = self.anchors(horizontal);
+        let (top, right, bottom, left) = self.outer_thickness(parent_width, parent_height);
+        let (margin_start, margin_end, parent_size) = if horizontal {
+            (left, right, parent_width)
+        } else {
+            (top, bottom, parent_height)
+        };
+        Some((parent_size - resolve(start) - resolve(end) - margin_start - margin_end).max(0))
+    }
+
+    ///offset of the object inside a parent of the given size when it is anchored to an edge,
+    ///the margin and border are kept outside the anchor distance
+    pub(crate) fn anchored_position(
+        &self,
+        parent_width: i64,
+        parent_height: i64,
+        horizontal: bool,
+    ) -> Option<i64> {
+        let resolve = |dimension: &Dimension| {
+            generic_dimension_calc(
+                dimension,
+                parent_width,
+                parent_height,
+                self.renderer_width,
+                self.renderer_height,
+                horizontal,
+            )
+        };
+        let (start, end) = self.anchors(horizontal);
+        let (top, right, bottom, left) = self.outer_thickness(parent_width, parent_height);
+        let (margin_start, margin_end, parent_size, size) = if horizontal {
+            (left, right, parent_width, self.calculated_width)
+        } else {
+            (top, bottom, parent_height, self.calculated_height)
+        };
+        if *start != Dimension::Auto {
+            Some(resolve(start) + margin_start)
+        } else if *end != Dimension::Auto {
+            Some(parent_size - resolve(end) - size - margin_end)
+        } else {
+            None
+        }
+    }
+
+    ///size of the children as seen by a layout, borders included,
+    ///without a size yet the children are measured by their intrinsic size
+    fn layout_items(
+        &self,
+        size: Option<(i64, i64)>,
+        renderer_width: i64,
+        renderer_height: i64,
+    ) -> Vec<LayoutItem> {
+        let (width, height) = size.unwrap_or((0, 0));
+        self.flow_children()
+            .iter()
+            .map(|child_cell| {
+                let child = child_cell.try_read().unwrap();
+                let (child_width, child_height) = match size {
+                    Some(_) => {
+                        child.natural_geometry(width, height, renderer_width, renderer_height)
+                    }
+                    None => child.intrinsic_geometry(renderer_width, renderer_height),
+                };
+                let (top, right, bottom, left) = child.outer_thickness(width, height);
+                LayoutItem {
+                    width: child_width + left + right,
+                    height: child_height + top + bottom,
+                    grow: child.style.flex_grow,
+                    shrink: child.style.flex_shrink,
+                    stretch_width: child.width == Dimension::Auto,
+                    stretch_height: child.height == Dimension::Auto,
+                }
+            })
+            .collect()
+    }
+
+    fn grid_tracks(
+        &self,
+        tracks: &[GridTrack],
+        width: i64,
+        height: i64,
+        renderer_width: i64,
+        renderer_height: i64,
+        horizontal: bool,
+    ) -> Vec<Track> {
+        tracks
+            .iter()
+            .map(|track| match track {
+                GridTrack::Size(Dimension::Auto) => Track::Auto,
+                GridTrack::Size(dimension) => Track::Fixed(generic_dimension_calc(
+                    dimension,
+                    width,
+                    height,
+                    renderer_width,
+                    renderer_height,
+                    horizontal,
+                )),
+                GridTrack::Fr(fraction) => Track::Fr(*fraction),
+            })
+            .collect()
+    }
+
+    fn grid_cells(&self, grid: &GridLayout) -> Vec<GridPlacement> {
+        let placements: Vec<Option<GridPlacement>> = self
+            .flow_children()
+            .iter()
+            .map(|child| child.try_read().unwrap().style.grid_placement)
+            .collect();
+        layout::grid_cells(&placements, grid.columns.len())
+    }
+
+    ///size needed by the children when placed by a layout
+    fn layout_content_size(
+        &self,
+        parent_width: i64,
+        parent_height: i64,
+        renderer_width: i64,
+        renderer_height: i64,
+    ) -> Option<(i64, i64)> {
+        let gap = |dimension: &Dimension, horizontal: bool| {
+            generic_dimension_calc(
+                dimension,
+                parent_width,
+                parent_height,
+                renderer_width,
+                renderer_height,
+                horizontal,
+            )
+        };
+        let items = || self.layout_items(None, renderer_width, renderer_height);
+        match &self.style.layout {
+            Layout::None => None,
+            Layout::Flex(flex) => Some(layout::flex_content_size(
+                flex,
+                gap(&flex.gap, flex.direction == FlexDirection::Row),
+                &items(),
+            )),
+            Layout::Grid(grid) => Some(layout::grid_content_size(
+                &self.grid_tracks(&grid.columns, 0, 0, renderer_width, renderer_height, true),
+                &self.grid_tracks(&grid.rows, 0, 0, renderer_width, renderer_height, false),
+                (gap(&grid.column_gap, true), gap(&grid.row_gap, false)),
+                &items(),
+                &self.grid_cells(grid),
+            )),
+        }
+    }
+
+    ///size ignoring the parent's layout
+    pub(crate) fn natural_geometry(
+        &self,
+        parent_width: i64,
+        parent_height: i64,
+        renderer_width: i64,
+        renderer_height: i64,
+    ) -> (i64, i64) {
+        self.measure(
+            parent_width,
+            parent_height,
+            renderer_width,
+            renderer_height,
+            false,
+        )
+    }
+
+    ///content based size used before the parent's size is known,
+    ///values relative to the parent are treated as auto like in css intrinsic sizing
+    pub(crate) fn intrinsic_geometry(
+        &self,
+        renderer_width: i64,
+        renderer_height: i64,
+    ) -> (i64, i64) {
+        self.measure(0, 0, renderer_width, renderer_height, true)
+    }
+
+    ///size and offset of a child as seen by this object's auto size, margins included
+    fn child_extent(
+        &self,
+        child: &RendererObjectValue,
+        renderer_width: i64,
+        renderer_height: i64,
+        intrinsic: bool,
+    ) -> (i64, i64) {
+        let (margin_top, margin_right, margin_bottom, margin_left) =
+            child
+                .style
+                .margin
+                .resolve(0, 0, renderer_width, renderer_height);
+        //percent offsets resolve against a size that isn't known yet
+        let offset = |horizontal: bool| -> i64 {
+            let position = if horizontal { &child.x } else { &child.y };
+            let (start, end) = child.anchors(horizontal);
+            [position, start, end]
+                .into_iter()
+                .filter(|dimension| !dimension.is_parent_relative())
+                .map(|dimension| {
+                    generic_dimension_calc(
+                        dimension,
+                        0,
+                        0,
+                        renderer_width,
+                        renderer_height,
+                        horizontal,
+                    )
+                })
+                .sum()
+        };
+        //children depending on this object are only processed once its size is known
+        let (width, height) = if intrinsic
+            || child.size_depends_on(true, Dimension::is_parent_relative)
+            || child.size_depends_on(false, Dimension::is_parent_relative)
+        {
+            child.intrinsic_geometry(renderer_width, renderer_height)
+        } else {
+            (child.calculated_width, child.calculated_height)
+        };
+        (
+            offset(true) + width + margin_left + margin_right,
+            offset(false) + height + margin_top + margin_bottom,
+        )
+    }
+
+    ///size needed by the children, each child is measured once for both axes
+    fn children_size(
+        &self,
+        parent_width: i64,
+        parent_height: i64,
+        renderer_width: i64,
+        renderer_height: i64,
+        intrinsic: bool,
+    ) -> (i64, i64) {
+        self.layout_content_size(parent_width, parent_height, renderer_width, renderer_height)
+            .unwrap_or_else(|| {
+                self.flow_children()
+                    .iter()
+                    .map(|child| {
+                        self.child_extent(
+                            &child.try_read().unwrap(),
+                            renderer_width,
+                            renderer_height,
+                            intrinsic,
+                        )
+                    })
+                    .fold((0, 0), |(width, height), (child_width, child_height)| {
+                        (width.max(child_width), height.max(child_height))
+                    })
+            })
+    }
+
+    fn measure(
+        &self,
+        parent_width: i64,
+        parent_height: i64,
+        renderer_width: i64,
+        renderer_height: i64,
+        intrinsic: bool,
+    ) -> (i64, i64) {
+        let (padding_top, padding_right, padding_bottom, padding_left) = self
+            .style
+            .padding
+            .resolve(parent_width, parent_height, renderer_width, renderer_height);
+        let auto = Dimension::Auto;
+        let resolved = |dimension| match intrinsic && Dimension::is_parent_relative(dimension) {
+            true => &auto,
+            false => dimension,
+        };
+        let (width_dimension, height_dimension) = (resolved(&self.width), resolved(&self.height));
+        let (content_width, content_height) =
+            if *width_dimension == Dimension::Auto || *height_dimension == Dimension::Auto {
+                self.children_size(
+                    parent_width,
+                    parent_height,
+                    renderer_width,
+                    renderer_height,
+                    intrinsic,
+                )
+            } else {
+                (0, 0)
+            };
+
         let mut width = generic_dimension_calc(
-            &self.width,
+            width_dimension,
             parent_width,
             parent_height,
             renderer_width,
@@ -230,52 +778,21 @@ impl RendererObjectValue {
             true,
         );
 
-        if self.width == Dimension::Auto {
-            for child in &self.children {
-                let child_ref = child.try_read().unwrap();
-                match (&child_ref.x, &child_ref.width) {
-                    //supported dimension types
-                    (
-                        Dimension::Auto
-                        | Dimension::Pixel(_)
-                        | Dimension::VW(_)
-                        | Dimension::VH(_)
-                        | Dimension::VMin(_)
-                        | Dimension::VMax(_),
-                        Dimension::Auto
-                        | Dimension::Pixel(_)
-                        | Dimension::VW(_)
-                        | Dimension::VH(_)
-                        | Dimension::VMin(_)
-                        | Dimension::VMax(_),
-                    ) => {
-                        width = width.max(
-                            child_ref.calculated_width
-                                + generic_dimension_calc(
-                                    &child_ref.x,
-                                    0,
-                                    0,
-                                    renderer_width,
-                                    renderer_height,
-                                    true,
-                                ),
-                        )
-                    }
-                    _ => (),
-                }
-            }
+        if *width_dimension == Dimension::Auto {
+            width = width.max(content_width);
             for line in &self.text {
-                width = width.max(line.len() as i64);
+                width = width.max(text::expand_tabs(line, self.style.tab_width).len() as i64);
             }
             for frame in &self.animation {
                 for line in frame {
                     width = width.max(line.len() as i64);
                 }
             }
+            width += padding_left + padding_right;
         }
 
         let mut height = generic_dimension_calc(
-            &self.height,
+            height_dimension,
             parent_width,
             parent_height,
             renderer_width,
@@ -283,47 +800,48 @@ impl RendererObjectValue {
             false,
         );
 
-        if self.height == Dimension::Auto {
-            for child in &self.children {
-                let child_ref = child.try_read().unwrap();
-                match (&child_ref.y, &child_ref.height) {
-                    //supported dimension types
-                    (
-                        Dimension::Auto
-                        | Dimension::Pixel(_)
-                        | Dimension::VW(_)
-                        | Dimension::VH(_)
-                        | Dimension::VMin(_)
-                        | Dimension::VMax(_),
-                        Dimension::Auto
-                        | Dimension::Pixel(_)
-                        | Dimension::VW(_)
-                        | Dimension::VH(_)
-                        | Dimension::VMin(_)
-                        | Dimension::VMax(_),
-                    ) => {
-                        height = height.max(
-                            child_ref.calculated_height
-                                + generic_dimension_calc(
-                                    &child_ref.y,
-                                    0,
-                                    0,
-                                    renderer_width,
-                                    renderer_height,
-                                    false,
-                                ),
-                        )
-                    }
-                    _ => (),
-                }
-            }
-            height = height.max(self.text.len() as i64);
+        if *height_dimension == Dimension::Auto {
+            height = height.max(content_height);
+            //the text is reflowed at the width the object ends up with
+            let constrained_width = self
+                .constrain_size(
+                    (width, 0),
+                    parent_width,
+                    parent_height,
+                    renderer_width,
+                    renderer_height,
+                    intrinsic,
+                )
+                .0;
+            let text_width = match intrinsic {
+                true => constrained_width,
+                false => self.layout_width.unwrap_or(constrained_width),
+            } - padding_left
+                - padding_right;
+            height = height.max(self.text_lines(text_width).len() as i64);
             for frame in &self.animation {
                 height = height.max(frame.len() as i64);
             }
+            height += padding_top + padding_bottom;
         }
 
-        (width, height)
+        //the height is derived unless the width is the only auto axis
+        let (width, height) = match (
+            *width_dimension == Dimension::Auto,
+            *height_dimension == Dimension::Auto,
+        ) {
+            (false, false) => (width, height),
+            (true, false) => self.keep_aspect_ratio((width, height), false),
+            _ => self.keep_aspect_ratio((width, height), true),
+        };
+        self.constrain_size(
+            (width, height),
+            parent_width,
+            parent_height,
+            renderer_width,
+            renderer_height,
+            intrinsic,
+        )
     }
 
     pub(crate) fn process_geometry(
@@ -336,77 +854,37 @@ impl RendererObjectValue {
     ) {
         if renderer_width != self.renderer_width {
             self.renderer_width = renderer_width;
-            match (&self.x, &self.width) {
-                (
-                    Dimension::VW(_) | Dimension::VH(_) | Dimension::VMin(_) | Dimension::VMax(_),
-                    _,
-                )
-                | (
-                    _,
-                    Dimension::VW(_) | Dimension::VH(_) | Dimension::VMin(_) | Dimension::VMax(_),
-                ) => self.update_size = true,
-                _ => (),
+            if self.x.is_renderer_relative()
+                || self.size_depends_on(true, Dimension::is_renderer_relative)
+            {
+                self.update_size = true;
             }
         }
 
         if renderer_height != self.renderer_height {
             self.renderer_height = renderer_height;
-            match (&self.y, &self.height) {
-                (
-                    Dimension::VW(_) | Dimension::VH(_) | Dimension::VMin(_) | Dimension::VMax(_),
-                    _,
-                )
-                | (
-                    _,
-                    Dimension::VW(_) | Dimension::VH(_) | Dimension::VMin(_) | Dimension::VMax(_),
-                ) => self.update_size = true,
-                _ => (),
+            if self.y.is_renderer_relative()
+                || self.size_depends_on(false, Dimension::is_renderer_relative)
+            {
+                self.update_size = true;
             }
         }
 
         if parent_width != self.parent_width {
             self.parent_width = parent_width;
-            match (&self.x, &self.width) {
-                (
-                    Dimension::Percent(_)
-                    | Dimension::PW(_)
-                    | Dimension::PH(_)
-                    | Dimension::PMin(_)
-                    | Dimension::PMax(_),
-                    _,
-                )
-                | (
-                    _,
-                    Dimension::Percent(_)
-                    | Dimension::PW(_)
-                    | Dimension::PH(_)
-                    | Dimension::PMin(_)
-                    | Dimension::PMax(_),
-                ) => self.update_size = true,
-                _ => (),
+            if self.x.is_parent_relative()
+                || self.size_depends_on(true, Dimension::is_parent_relative)
+            {
+                self.update_size = true;
             }
         }
 
         if parent_height != self.parent_height {
             self.parent_height = parent_height;
-            match (&self.y, &self.height) {
-                (
-                    Dimension::Percent(_)
-                    | Dimension::PW(_)
-                    | Dimension::PH(_)
-                    | Dimension::PMin(_)
-                    | Dimension::PMax(_),
-                    _,
-                )
-                | (
-                    _,
-                    Dimension::Percent(_)
-                    | Dimension::PW(_)
-                    | Dimension::PH(_)
-                    | Dimension::PMin(_)
-                    | Dimension::PMax(_),
-                ) => self.update_size = true,
-                _ => (),
+            if self.y.is_parent_relative()
+                || self.size_depends_on(false, Dimension::is_parent_relative)
+            {
+                self.update_size = true;
             }
         }
 
@@ -414,47 +892,42 @@ impl RendererObjectValue {
         if self.update_content {
             for child_cell in &self.children {
                 let mut child = child_cell.write().unwrap();
-                match (child.width, child.height) {
-                    //evil match that selects children with parent-dependent values
-                    (
-                        Dimension::Percent(_)
-                        | Dimension::PW(_)
-                        | Dimension::PH(_)
-                        | Dimension::PMin(_)
-                        | Dimension::PMax(_),
-                        _,
-                    )
-                    | (
-                        _,
-                        Dimension::Percent(_)
-                        | Dimension::PW(_)
-                        | Dimension::PH(_)
-                        | Dimension::PMin(_)
-                        | Dimension::PMax(_),
-                    ) => (),
-                    _ => child.process_geometry(
+                child.set_cell_aspect(self.cell_aspect);
+                child.set_compositing(self.compositing);
+                if self.style.layout == Layout::None && child.clear_layout() {
+                    child.update_size = true;
+                }
+                if child.is_fixed() {
+                    child.process_geometry(
+                        renderer_width,
+                        renderer_height,
                         renderer_width,
                         renderer_height,
-                        0,
-                        0,
                         renderer_padding,
-                    ),
+                    );
+                    continue;
+                }
+                //children with parent-dependent values are processed after the size is known
+                if !child.size_depends_on(true, Dimension::is_parent_relative)
+                    && !child.size_depends_on(false, Dimension::is_parent_relative)
+                {
+                    child.process_geometry(renderer_width, renderer_height, 0, 0, renderer_padding);
                 }
             }
         }
 
-        if self.update_content {
-            if self.width == Dimension::Auto || self.height == Dimension::Auto {
-                let (new_width, new_height) = self.calculate_geometry(
-                    parent_width,
-                    parent_height,
-                    renderer_width,
-                    renderer_height,
-                );
-                if (new_width, new_height) != (self.calculated_width, self.calculated_height) {
-                    (self.calculated_width, self.calculated_height) = (new_width, new_height);
-                    self.update_size = true;
-                }
+        if (self.update_content || self.update_size)
+            && (self.width == Dimension::Auto || self.height == Dimension::Auto)
+        {
+            let (new_width, new_height) = self.calculate_geometry(
+                parent_width,
+                parent_height,
+                renderer_width,
+                renderer_height,
+            );
+            if (new_width, new_height) != (self.calculated_width, self.calculated_height) {
+                (self.calculated_width, self.calculated_height) = (new_width, new_height);
+                self.update_size = true;
             }
         }
 
@@ -494,37 +967,249 @@ impl RendererObjectValue {
             self.update_size = false;
         }
 
-        //update children dependent on current object
-        if self.update_content {
+        if self.update_content && self.style.layout != Layout::None {
+            self.arrange_children(renderer_width, renderer_height, renderer_padding);
+        } else if self.update_content {
+            //update children dependent on current object
             for child_cell in &self.children {
                 let mut child = child_cell.write().unwrap();
-                match (child.width, child.height) {
-                    (
-                        Dimension::Percent(_)
-                        | Dimension::PW(_)
-                        | Dimension::PH(_)
-                        | Dimension::PMin(_)
-                        | Dimension::PMax(_),
-                        _,
-                    )
-                    | (
-                        _,
-                        Dimension::Percent(_)
-                        | Dimension::PW(_)
-                        | Dimension::PH(_)
-                        | Dimension::PMin(_)
-                        | Dimension::PMax(_),
-                    ) => child.process_geometry(
+                if !child.is_fixed()
+                    && (child.size_depends_on(true, Dimension::is_parent_relative)
+                        || child.size_depends_on(false, Dimension::is_parent_relative))
+                {
+                    child.process_geometry(
                         renderer_width,
                         renderer_height,
                         self.calculated_width,
                         self.calculated_height,
                         renderer_padding,
-                    ),
-                    _ => (),
+                    );
                 }
             }
         }
+
+        if self.style.overflow == Overflow::Scroll {
+            let (max_scroll_x, max_scroll_y) = self.max_scroll();
+            let scroll_position = (
+                self.scroll_x.clamp(0, max_scroll_x),
+                self.scroll_y.clamp(0, max_scroll_y),
+            );
+            if scroll_position != self.scroll_position {
+                self.scroll_position = scroll_position;
+                self.update_content = true;
+            }
+        }
+    }
+
+    pub(crate) fn is_fixed(&self) -> bool {
+        self.style.position == Position::Fixed
+    }
+
+    ///true if the object and its children are drawn
+    pub(crate) fn is_shown(&self) -> bool {
+        self.visible && self.displayed
+    }
+
+    ///children placed inside this object, fixed and undisplayed children are left out
+    fn flow_children(&self) -> Vec<Arc<RwLock<RendererObjectValue>>> {
+        self.children
+            .iter()
+            .filter(|child| {
+                let child = child.try_read().unwrap();
+                child.displayed && !child.is_fixed()
+            })
+            .cloned()
+            .collect()
+    }
+
+    ///shown children that aren't fixed in compositing order, by z index and then by insertion
+    fn painted_children(&self) -> Vec<Arc<RwLock<RendererObjectValue>>> {
+        let mut children: Vec<Arc<RwLock<RendererObjectValue>>> = self
+            .flow_children()
+            .into_iter()
+            .filter(|child| child.try_read().unwrap().visible)
+            .collect();
+        children.sort_by_key(|child| child.try_read().unwrap().style.z_index);
+        children
+    }
+
+    ///fixed objects below the object in compositing order, by z index and then by tree order
+    pub(crate) fn fixed_descendants(&self) -> Vec<Arc<RwLock<RendererObjectValue>>> {
+        let mut fixed: Vec<Arc<RwLock<RendererObjectValue>>> = Vec::new();
+        self.collect_fixed(&mut fixed);
+        fixed.sort_by_key(|child| child.read().unwrap().style.z_index);
+        fixed
+    }
+
+    fn collect_fixed(&self, fixed: &mut Vec<Arc<RwLock<RendererObjectValue>>>) {
+        for child_cell in &self.children {
+            let child = child_cell.read().unwrap();
+            if !child.is_shown() {
+                continue;
+            }
+            if child.is_fixed() {
+                fixed.push(child_cell.clone());
+            }
+            child.collect_fixed(fixed);
+        }
+    }
+
+    ///returns true if there was anything to clear
+    fn clear_layout(&mut self) -> bool {
+        let placed = self.layout_width.is_some()
+            || self.layout_height.is_some()
+            || self.layout_position.is_some();
+        (self.layout_width, self.layout_height, self.layout_position) = (None, None, None);
+        placed
+    }
+
+    ///text broken into lines and cut to the content width by the text style
+    pub(crate) fn text_lines(&self, width: i64) -> Vec<Vec<char>> {
+        let justify = self.style.text_align == Some(TextAlign::Justify);
+        if self.style.text_wrap == TextWrap::None
+            && self.style.ellipsis.is_none()
+            && !justify
+            && !self.text.iter().any(|line| line.contains(&'\t'))
+        {
+            return self.text.clone();
+        }
+        let width = width.max(0) as usize;
+        self.text
+            .iter()
+            .flat_map(|line| {
+                let line = text::expand_tabs(line, self.style.tab_width);
+                let mut lines = text::wrap_line(&line, width, self.style.text_wrap);
+                if let Some(ellipsis) = self.style.ellipsis {
+                    for line in lines.iter_mut() {
+                        *line = text::truncate_line(line, width, ellipsis);
+                    }
+                }
+                //the last line of a paragraph isn't stretched
+                if justify {
+                    let last = lines.len() - 1;
+                    for line in lines[..last].iter_mut() {
+                        *line = text::justify_line(line, width);
+                    }
+                }
+                lines
+            })
+            .collect()
+    }
+
+    fn text_align(&self) -> TextAlign {
+        self.style
+            .text_align
+            .unwrap_or(match self.style.internal_alignment_x {
+                AlignmentX::Left => TextAlign::Left,
+                AlignmentX::Center => TextAlign::Center,
+                AlignmentX::Right => TextAlign::Right,
+            })
+    }
+
+    ///padding resolved against the parent, as (top, right, bottom, left)
+    pub(crate) fn padding(&self) -> (i64, i64, i64, i64) {
+        self.style.padding.resolve(
+            self.parent_width,
+            self.parent_height,
+            self.renderer_width,
+            self.renderer_height,
+        )
+    }
+
+    ///margin and border around the object, as (top, right, bottom, left)
+    fn outer_thickness(&self, parent_width: i64, parent_height: i64) -> (i64, i64, i64, i64) {
+        let (border_top, border_right, border_bottom, border_left) = self.style.border.thickness();
+        let (margin_top, margin_right, margin_bottom, margin_left) = self.style.margin.resolve(
+            parent_width,
+            parent_height,
+            self.renderer_width,
+            self.renderer_height,
+        );
+        (
+            border_top + margin_top,
+            border_right + margin_right,
+            border_bottom + margin_bottom,
+            border_left + margin_left,
+        )
+    }
+
+    ///places the children with the layout and processes them against the result
+    fn arrange_children(
+        &mut self,
+        renderer_width: i64,
+        renderer_height: i64,
+        renderer_padding: i64,
+    ) {
+        let (padding_top, padding_right, padding_bottom, padding_left) = self.padding();
+        let width = (self.calculated_width - padding_left - padding_right).max(0);
+        let height = (self.calculated_height - padding_top - padding_bottom).max(0);
+        let gap = |dimension: &Dimension, horizontal: bool| {
+            generic_dimension_calc(
+                dimension,
+                width,
+                height,
+                renderer_width,
+                renderer_height,
+                horizontal,
+            )
+        };
+        let items = self.layout_items(Some((width, height)), renderer_width, renderer_height);
+        let placements = match &self.style.layout {
+            Layout::None => return,
+            Layout::Flex(flex) => layout::flex(
+                flex,
+                width,
+                height,
+                gap(&flex.gap, flex.direction == FlexDirection::Row),
+                &items,
+            ),
+            Layout::Grid(grid) => layout::grid(
+                &self.grid_tracks(
+                    &grid.columns,
+                    width,
+                    height,
+                    renderer_width,
+                    renderer_height,
+                    true,
+                ),
+                &self.grid_tracks(
+                    &grid.rows,
+                    width,
+                    height,
+                    renderer_width,
+                    renderer_height,
+                    false,
+                ),
+                width,
+                height,
+                (gap(&grid.column_gap, true), gap(&grid.row_gap, false)),
+                &items,
+                &self.grid_cells(grid),
+            ),
+        };
+
+        for (child_cell, placement) in self.flow_children().iter().zip(placements) {
+            let mut child = child_cell.write().unwrap();
+            let (top, right, bottom, left) =
+                child.outer_thickness(self.calculated_width, self.calculated_height);
+            let width = Some((placement.width - left - right).max(0));
+            let height = Some((placement.height - top - bottom).max(0));
+            if (child.layout_width, child.layout_height) != (width, height) {
+                (child.layout_width, child.layout_height) = (width, height);
+                child.update_size = true;
+            }
+            child.layout_position = Some((
+                padding_left + placement.x + left,
+                padding_top + placement.y + top,
+            ));
+            child.process_geometry(
+                renderer_width,
+                renderer_height,
+                self.calculated_width,
+                self.calculated_height,
+                renderer_padding,
+            );
+        }
     }
 
     fn draw_text(&mut self, renderer_padding: i64) {
@@ -541,27 +1226,35 @@ impl RendererObjectValue {
             .min(self.calculated_height)
             .max(0);
 
-        let text_height: usize = self.text.len();
-        let alignment_offset_y: i64 = match self.style.internal_alignment_y {
-            AlignmentY::Top => 0,
-            AlignmentY::Center => self.calculated_height / 2 - text_height as i64 / 2,
-            AlignmentY::Bottom => self.calculated_height - text_height as i64,
-        };
+        let (padding_top, padding_right, padding_bottom, padding_left) = self.padding();
+        let content_width = self.calculated_width - padding_left - padding_right;
+        let content_height = self.calculated_height - padding_top - padding_bottom;
+        let (scroll_x, scroll_y) = self.scroll_offset();
+        let text = self.text_lines(content_width);
+
+        let text_height: usize = text.len();
+        let alignment_offset_y: i64 = padding_top - scroll_y
+            + match self.style.internal_alignment_y {
+                AlignmentY::Top => 0,
+                AlignmentY::Center => content_height / 2 - text_height as i64 / 2,
+                AlignmentY::Bottom => content_height - text_height as i64,
+            };
         let text_start_y = start_y.max(alignment_offset_y);
         let text_end_y = end_y.min(alignment_offset_y + text_height as i64);
 
         for i in text_start_y..text_end_y {
-            let line_width: usize = self.text[(i - alignment_offset_y) as usize].len();
-            let alignment_offset_x: i64 = match self.style.internal_alignment_x {
-                AlignmentX::Left => 0,
-                AlignmentX::Center => self.calculated_width / 2 - line_width as i64 / 2,
-                AlignmentX::Right => self.calculated_width - line_width as i64,
-            };
+            let line_width: usize = text[(i - alignment_offset_y) as usize].len();
+            let alignment_offset_x: i64 = padding_left - scroll_x
+                + match self.text_align() {
+                    TextAlign::Left | TextAlign::Justify => 0,
+                    TextAlign::Center => content_width / 2 - line_width as i64 / 2,
+                    TextAlign::Right => content_width - line_width as i64,
+                };
             let line_start_x = start_x.max(alignment_offset_x);
             let line_end_x = end_x.min(alignment_offset_x + line_width as i64);
             for j in line_start_x..line_end_x {
                 let new_val =
-                    self.text[(i - alignment_offset_y) as usize][(j - alignment_offset_x) as usize];
+                    text[(i - alignment_offset_y) as usize][(j - alignment_offset_x) as usize];
                 if new_val != '\0' {
                     self.buffer[i as usize][j as usize].value = new_val;
                 }
@@ -583,11 +1276,13 @@ impl RendererObjectValue {
             .min(self.calculated_height)
             .max(0);
 
+        let (scroll_x, scroll_y) = self.scroll_offset();
         for i in start_y..end_y {
-            let chars: &Vec<char> = &self.pattern[i as usize % self.pattern.len()];
+            let chars: &Vec<char> =
+                &self.pattern[(i + scroll_y).rem_euclid(self.pattern.len() as i64) as usize];
             if chars.len() > 0 {
                 for j in start_x..end_x {
-                    let new_val = chars[j as usize % chars.len()];
+                    let new_val = chars[(j + scroll_x).rem_euclid(chars.len() as i64) as usize];
                     if new_val != '\0' {
                         self.buffer[(i - start_y) as usize][(j - start_x) as usize].value = new_val;
                     }
@@ -612,24 +1307,31 @@ impl RendererObjectValue {
 
         let frame = &self.animation[current_animation_frame as usize % self.animation.len()];
 
-        let frame_height: usize = frame.len();
-        let alignment_offset_y: i64 = match self.style.internal_alignment_y {
-            AlignmentY::Top => 0,
-            AlignmentY::Center => self.calculated_height / 2 - frame_height as i64 / 2,
-            AlignmentY::Bottom => self.calculated_height - frame_height as i64,
-        };
-        let frame_start_y = start_y.max(alignment_offset_y) as usize;
-        let frame_end_y = end_y.min(alignment_offset_y + frame_height as i64) as usize;
+        let (padding_top, padding_right, padding_bottom, padding_left) = self.padding();
+        let content_width = self.calculated_width - padding_left - padding_right;
+        let content_height = self.calculated_height - padding_top - padding_bottom;
+        let (scroll_x, scroll_y) = self.scroll_offset();
 
-        for j in frame_start_y..frame_end_y as usize {
-            let line_width: usize = frame[j - alignment_offset_y as usize].len();
-            let alignment_offset_x: i64 = match self.style.internal_alignment_x {
-                AlignmentX::Left => 0,
-                AlignmentX::Center => self.calculated_width / 2 - line_width as i64 / 2,
-                AlignmentX::Right => self.calculated_width - line_width as i64,
+        let frame_height: usize = frame.len();
+        let alignment_offset_y: i64 = padding_top - scroll_y
+            + match self.style.internal_alignment_y {
+                AlignmentY::Top => 0,
+                AlignmentY::Center => content_height / 2 - frame_height as i64 / 2,
+                AlignmentY::Bottom => content_height - frame_height as i64,
             };
+        let frame_start_y = start_y.max(alignment_offset_y) as usize;
+        let frame_end_y = end_y.min(alignment_offset_y + frame_height as i64).max(0) as usize;
+
+        for j in frame_start_y..frame_end_y {
+            let line_width: usize = frame[(j as i64 - alignment_offset_y) as usize].len();
+            let alignment_offset_x: i64 = padding_left - scroll_x
+                + match self.style.internal_alignment_x {
+                    AlignmentX::Left => 0,
+                    AlignmentX::Center => content_width / 2 - line_width as i64 / 2,
+                    AlignmentX::Right => content_width - line_width as i64,
+                };
             let line_start_x = start_x.max(alignment_offset_x) as usize;
-            let line_end_x = end_x.min(alignment_offset_x + line_width as i64) as usize;
+            let line_end_x = end_x.min(alignment_offset_x + line_width as i64).max(0) as usize;
             for i in line_start_x..line_end_x {
                 self.buffer[j][i].value = frame[(j as i64 - alignment_offset_y) as usize]
                     [(i as i64 - alignment_offset_x) as usize];
@@ -658,11 +1360,12 @@ impl RendererObjectValue {
         let frame = &self.animated_pattern
             [current_animated_pattern_frame as usize % self.animated_pattern.len()];
 
+        let (scroll_x, scroll_y) = self.scroll_offset();
         for i in start_y..end_y {
-            let chars: &Vec<char> = &frame[i as usize % frame.len()];
+            let chars: &Vec<char> = &frame[(i + scroll_y).rem_euclid(frame.len() as i64) as usize];
             if chars.len() > 0 {
                 for j in start_x..end_x {
-                    let new_val = chars[j as usize % chars.len()];
+                    let new_val = chars[(j + scroll_x).rem_euclid(chars.len() as i64) as usize];
                     if new_val != '\0' {
                         self.buffer[(i - start_y) as usize][(j - start_x) as usize].value = new_val;
                     }
@@ -687,6 +1390,7 @@ impl RendererObjectValue {
             .min(self.calculated_height)
             .max(0);
 
+        let compositing = self.compositing;
         for color_area in &self.colors {
             let color_x = generic_dimension_calc(
                 &color_area.x,
@@ -755,18 +1459,25 @@ impl RendererObjectValue {
 
             for i in color_start_y.max(0)..color_end_y.min(rendered_height) {
                 for j in color_start_x.max(0)..color_end_x.min(rendered_width) {
+                    let pixel = &mut self.buffer[i as usize][j as usize];
+                    let color = match color_area.color {
+                        Colors::CURRENT_COLOR => pixel.foreground,
+                        color => color,
+                    };
                     match color_area.layer {
                         ColorLayer::Background => {
-                            self.buffer[i as usize][j as usize].background = self.buffer[i as usize]
-                                [j as usize]
-                                .background
-                                .with_overlay(color_area.color)
+                            pixel.background = pixel.background.with_blend(
+                                color,
+                                color_area.blend_mode,
+                                compositing,
+                            )
                         }
                         ColorLayer::Foreground => {
-                            self.buffer[i as usize][j as usize].foreground = self.buffer[i as usize]
-                                [j as usize]
-                                .foreground
-                                .with_overlay(color_area.color)
+                            pixel.foreground = pixel.foreground.with_blend(
+                                color,
+                                color_area.blend_mode,
+                                compositing,
+                            )
                         }
                     }
                 }
@@ -774,6 +1485,260 @@ impl RendererObjectValue {
         }
     }
 
+    ///size of the unscrolled content, padding included
+    pub(crate) fn content_size(&self) -> (i64, i64) {
+        let (padding_top, padding_right, padding_bottom, padding_left) = self.padding();
+        let (scroll_x, scroll_y) = self.scroll_offset();
+        let text = self.text_lines(self.calculated_width - padding_left - padding_right);
+        let mut width: i64 = padding_left + padding_right;
+        let mut height: i64 = padding_top + text.len() as i64 + padding_bottom;
+        for line in &text {
+            width = width.max(padding_left + line.len() as i64 + padding_right);
+        }
+        for child_cell in &self.flow_children() {
+            let child = child_cell.read().unwrap();
+            let (x, y) = self.child_position(&child);
+            let (_, right, bottom, _) =
+                child.outer_thickness(self.calculated_width, self.calculated_height);
+            width = width.max(x + scroll_x + child.calculated_width + right + padding_right);
+            height = height.max(y + scroll_y + child.calculated_height + bottom + padding_bottom);
+        }
+        (width, height)
+    }
+
+    ///which scrollbars are drawn, as (vertical, horizontal)
+    pub(crate) fn scrollbars(&self) -> (bool, bool) {
+        if self.style.scrollbar.is_none() || self.style.overflow != Overflow::Scroll {
+            return (false, false);
+        }
+        let (content_width, content_height) = self.content_size();
+        let vertical = content_height > self.calculated_height;
+        let horizontal = content_width > self.calculated_width - vertical as i64;
+        //the horizontal bar may hide the last row
+        let vertical = vertical || (horizontal && content_height > self.calculated_height - 1);
+        (vertical, horizontal)
+    }
+
+    ///area left for the content once the scrollbars are drawn
+    pub(crate) fn visible_size(&self) -> (i64, i64) {
+        let (vertical, horizontal) = self.scrollbars();
+        (
+            (self.calculated_width - vertical as i64).max(0),
+            (self.calculated_height - horizontal as i64).max(0),
+        )
+    }
+
+    pub(crate) fn max_scroll(&self) -> (i64, i64) {
+        let (content_width, content_height) = self.content_size();
+        let (visible_width, visible_height) = self.visible_size();
+        (
+            (content_width - visible_width).max(0),
+            (content_height - visible_height).max(0),
+        )
+    }
+
+    ///position of a descendant relative to this object's origin
+    pub(crate) fn offset_of(
+        object: &Arc<RwLock<RendererObjectValue>>,
+        target: &Arc<RwLock<RendererObjectValue>>,
+    ) -> Option<(i64, i64)> {
+        if Arc::ptr_eq(object, target) {
+            return Some((0, 0));
+        }
+        let value = object.read().unwrap();
+        value.children.iter().find_map(|child_cell| {
+            let (x, y) = value.child_position(&child_cell.read().unwrap());
+            RendererObjectValue::offset_of(child_cell, target)
+                .map(|(offset_x, offset_y)| (x + offset_x, y + offset_y))
+        })
+    }
+
+    fn draw_scrollbars(&mut self, renderer_padding: i64) {
+        let scrollbar = match self.style.scrollbar {
+            Some(scrollbar) => scrollbar,
+            None => return,
+        };
+        let (vertical, horizontal) = self.scrollbars();
+        let (content_width, content_height) = self.content_size();
+        let (visible_width, visible_height) = self.visible_size();
+        let (scroll_x, scroll_y) = self.scroll_offset();
+        let (max_scroll_x, max_scroll_y) = self.max_scroll();
+        let area = (
+            (-self.absolute_x - renderer_padding)
+                .min(self.calculated_width)
+                .max(0),
+            (-self.absolute_y - renderer_padding)
+                .min(self.calculated_height)
+                .max(0),
+            (-self.absolute_x + renderer_padding + self.renderer_width)
+                .min(self.calculated_width)
+                .max(0),
+            (-self.absolute_y + renderer_padding + self.renderer_height)
+                .min(self.calculated_height)
+                .max(0),
+        );
+
+        const LOWER_BLOCKS: [char; 7] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇'];
+        const LEFT_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+        let thumb = |pixel_value: char| Pixel {
+            value: pixel_value,
+            background: scrollbar.track_color,
+            foreground: scrollbar.thumb_color,
+        };
+        //partial cells are drawn with the thumb as the background and the track as the foreground
+        let inverted = |pixel_value: char| Pixel {
+            value: pixel_value,
+            background: scrollbar.thumb_color,
+            foreground: scrollbar.track_color,
+        };
+
+        if vertical {
+            let cells = thumb_cells(visible_height, content_height, scroll_y, max_scroll_y);
+            for (i, (covered, starts_inside)) in cells.into_iter().enumerate() {
+                let pixel = match (covered, starts_inside) {
+                    (0, _) => thumb(' '),
+                    (8, _) => thumb('█'),
+                    //the thumb covers the bottom of the cell
+                    (covered, true) => thumb(LOWER_BLOCKS[covered as usize - 1]),
+                    (covered, false) => inverted(LOWER_BLOCKS[7 - covered as usize]),
+                };
+                self.replace_pixel((i as i64, self.calculated_width - 1), &pixel, area);
+            }
+        }
+
+        if horizontal {
+            let cells = thumb_cells(visible_width, content_width, scroll_x, max_scroll_x);
+            for (j, (covered, starts_inside)) in cells.into_iter().enumerate() {
+                let pixel = match (covered, starts_inside) {
+                    (0, _) => thumb(' '),
+                    (8, _) => thumb('█'),
+                    //the thumb covers the right side of the cell
+                    (covered, true) => inverted(LEFT_BLOCKS[7 - covered as usize]),
+                    (covered, false) => thumb(LEFT_BLOCKS[covered as usize - 1]),
+                };
+                self.replace_pixel((self.calculated_height - 1, j as i64), &pixel, area);
+            }
+        }
+
+        if vertical && horizontal {
+            self.replace_pixel(
+                (self.calculated_height - 1, self.calculated_width - 1),
+                &thumb(' '),
+                area,
+            );
+        }
+    }
+
+    ///content offset, only applied when the overflow is scrolled
+    pub(crate) fn scroll_offset(&self) -> (i64, i64) {
+        match self.style.overflow {
+            Overflow::Scroll => self.scroll_position,
+            _ => (0, 0),
+        }
+    }
+
+    ///position of a child's top left corner relative to this object, alignment included
+    pub(crate) fn child_position(&self, child: &RendererObjectValue) -> (i64, i64) {
+        let child_x = generic_dimension_calc(
+            &child.x,
+            self.calculated_width,
+            self.calculated_height,
+            self.renderer_width,
+            self.renderer_height,
+            true,
+        );
+        let child_y = generic_dimension_calc(
+            &child.y,
+            self.calculated_width,
+            self.calculated_height,
+            self.renderer_width,
+            self.renderer_height,
+            false,
+        );
+
+        let (scroll_x, scroll_y) = self.scroll_offset();
+        let child_x = child_x - scroll_x;
+        let child_y = child_y - scroll_y;
+
+        //layouts replace alignment, the position only nudges the child
+        if let Some((layout_x, layout_y)) = child.layout_position {
+            return (layout_x + child_x, layout_y + child_y);
+        }
+
+        //children are aligned inside the padding, with their margin around them
+        let (padding_top, padding_right, padding_bottom, padding_left) = self.padding();
+        let (margin_top, margin_right, margin_bottom, margin_left) = child.style.margin.resolve(
+            self.calculated_width,
+            self.calculated_height,
+            self.renderer_width,
+            self.renderer_height,
+        );
+        let content_width = self.calculated_width - padding_left - padding_right;
+        let content_height = self.calculated_height - padding_top - padding_bottom;
+
+        //anchors are measured from the parent's edges, ignoring the padding
+        let anchored_x =
+            child.anchored_position(self.calculated_width, self.calculated_height, true);
+        let anchored_y =
+            child.anchored_position(self.calculated_width, self.calculated_height, false);
+
+        let alignment_offset_x: i64 = padding_left
+            + match match child.style.external_alignment_x {
+                Some(val) => val,
+                None => self.style.internal_alignment_x,
+            } {
+                AlignmentX::Left => margin_left,
+                AlignmentX::Center => {
+                    content_width / 2 - (child.calculated_width + margin_left + margin_right) / 2
+                        + margin_left
+                }
+                AlignmentX::Right => content_width - child.calculated_width - margin_right,
+            };
+
+        let alignment_offset_y: i64 = padding_top
+            + match match child.style.external_alignment_y {
+                Some(val) => val,
+                None => self.style.internal_alignment_y,
+            } {
+                AlignmentY::Top => margin_top,
+                AlignmentY::Center => {
+                    content_height / 2 - (child.calculated_height + margin_top + margin_bottom) / 2
+                        + margin_top
+                }
+                AlignmentY::Bottom => content_height - child.calculated_height - margin_bottom,
+            };
+
+        (
+            anchored_x.unwrap_or(alignment_offset_x) + child_x,
+            anchored_y.unwrap_or(alignment_offset_y) + child_y,
+        )
+    }
+
+    ///finds the deepest object containing the point, coordinates relative to the object's origin
+    pub(crate) fn hit_test(
+        object: &Arc<RwLock<RendererObjectValue>>,
+        x: i64,
+        y: i64,
+    ) -> Option<Arc<RwLock<RendererObjectValue>>> {
+        let value = object.read().unwrap();
+        if !value.is_shown()
+            || x < 0
+            || y < 0
+            || x >= value.calculated_width
+            || y >= value.calculated_height
+        {
+            return None;
+        }
+        //later children are composited on top
+        for child_cell in value.painted_children().iter().rev() {
+            let (child_x, child_y) = value.child_position(&child_cell.read().unwrap());
+            if let Some(hit) = RendererObjectValue::hit_test(child_cell, x - child_x, y - child_y) {
+                return Some(hit);
+            }
+        }
+        Some(object.clone())
+    }
+
     fn draw_children(&mut self, renderer_padding: i64) {
         let start_x: i64 = (-self.absolute_x - renderer_padding)
             .min(self.calculated_width)
@@ -788,67 +1753,32 @@ impl RendererObjectValue {
             .min(self.calculated_height)
             .max(0);
 
-        for child_cell in &self.children {
+        let compositing = self.compositing;
+        for child_cell in &self.painted_children() {
             let mut child = child_cell.write().unwrap();
-            let child_x = generic_dimension_calc(
-                &child.x,
-                self.calculated_width,
-                self.calculated_height,
-                self.renderer_width,
-                self.renderer_height,
-                true,
-            );
-            let child_y = generic_dimension_calc(
-                &child.y,
-                self.calculated_width,
-                self.calculated_height,
-                self.renderer_width,
-                self.renderer_height,
-                false,
-            );
-
+            let (child_left, child_top) = self.child_position(&child);
             let child_width = child.calculated_width;
             let child_height = child.calculated_height;
-
-            //use precalculated child
-            let alignment_offset_x: i64 = match match child.style.external_alignment_x {
-                Some(val) => val,
-                None => self.style.internal_alignment_x,
-            } {
-                AlignmentX::Left => 0,
-                AlignmentX::Center => self.calculated_width / 2 - child_width as i64 / 2,
-                AlignmentX::Right => self.calculated_width - child_width as i64,
-            };
-
-            let alignment_offset_y: i64 = match match child.style.external_alignment_y {
-                Some(val) => val,
-                None => self.style.internal_alignment_y,
-            } {
-                AlignmentY::Top => 0,
-                AlignmentY::Center => self.calculated_height / 2 - child_height as i64 / 2,
-                AlignmentY::Bottom => self.calculated_height - child_height as i64,
-            };
+            let blend_mode = child.style.blend_mode;
 
             let child_buffer = child.get_buffer(
-                self.absolute_x + alignment_offset_x + child_x,
-                self.absolute_y + alignment_offset_y + child_y,
+                self.absolute_x + child_left,
+                self.absolute_y + child_top,
                 renderer_padding,
             );
 
             //I have no idea why this correction works, it's a total bodge, but it's perfect
             let child_rendering_correction_x = child_width + renderer_padding * 2
                 - child_buffer.first().unwrap_or(&vec![]).len() as i64
-                - (child_width + child_x + alignment_offset_x - self.calculated_width)
+                - (child_width + child_left - self.calculated_width)
                     .clamp(0, (child_width - self.calculated_width).max(0));
             let child_rendering_correction_y = child_height + renderer_padding * 2
                 - child_buffer.len() as i64
-                - (child_height + child_y + alignment_offset_y - self.calculated_height)
+                - (child_height + child_top - self.calculated_height)
                     .clamp(0, (child_height - self.calculated_height).max(0));
 
-            let child_top = alignment_offset_y + child_y;
-            let child_bottom = alignment_offset_y + child_y + child_height - 1;
-            let child_left = alignment_offset_x + child_x;
-            let child_right = alignment_offset_x + child_x + child_width - 1;
+            let child_bottom = child_top + child_height - 1;
+            let child_right = child_left + child_width - 1;
 
             for i in (child_top + child_rendering_correction_y).max(start_y)
                 ..=child_bottom.min(end_y - 1)
@@ -857,106 +1787,143 @@ impl RendererObjectValue {
                     ..=child_right.min(end_x - 1)
                 {
                     self.buffer[(i - start_y) as usize][(j - start_x) as usize] =
-                        self.buffer[(i - start_y) as usize][(j - start_x) as usize].with_overlay(
+                        self.buffer[(i - start_y) as usize][(j - start_x) as usize].with_blend(
                             &child_buffer[(i - child_top - child_rendering_correction_y) as usize]
                                 [(j - child_left - child_rendering_correction_x) as usize],
+                            blend_mode,
+                            compositing,
                         );
                 }
             }
 
             //draw border around object
-            if child.style.border.top.value != '\0'
-                && child_top - 1 >= start_y
-                && child_top - 1 < end_y
-            {
-                for j in child_left.max(start_x)..=child_right.min(end_x - 1) {
-                    self.buffer[(child_top - 1 - start_y) as usize][(j - start_x) as usize] =
-                        self.buffer[(child_top - 1 - start_y) as usize][(j - start_x) as usize]
-                            .with_overlay(&child.style.border.top);
-                }
-            }
+            let area = (start_x, start_y, end_x, end_y);
+            self.draw_border(
+                &child.style.border,
+                (child_left, child_top, child_right, child_bottom),
+                area,
+                &[],
+            );
 
-            if child.style.border.bottom.value != '\0'
-                && child_bottom + 1 >= start_y
-                && child_bottom + 1 < end_y
-            {
-                for j in child_left.max(start_x)..=child_right.min(end_x - 1) {
-                    self.buffer[(child_bottom + 1 - start_y) as usize][(j - start_x) as usize] =
-                        self.buffer[(child_bottom + 1 - start_y) as usize][(j - start_x) as usize]
-                            .with_overlay(&child.style.border.bottom);
-                }
+            if child.style.overflow == Overflow::Visible {
+                let child_box = (
+                    child_left,
+                    child_top,
+                    child_left + child_width,
+                    child_top + child_height,
+                );
+                self.draw_overflow(&child, (child_left, child_top), area, &[child_box]);
             }
+        }
+    }
 
-            if child.style.border.left.value != '\0'
-                && child_left - 1 >= start_x
-                && child_left - 1 < end_x
-            {
-                for i in child_top.max(start_y)..=child_bottom.min(end_y - 1) {
-                    self.buffer[(i - start_y) as usize][(child_left - 1 - start_x) as usize] =
-                        self.buffer[(i - start_y) as usize][(child_left - 1 - start_x) as usize]
-                            .with_overlay(&child.style.border.left);
-                }
-            }
+    //overlays a pixel given in object coordinates, skipped boxes were already drawn by a child
+    fn overlay_pixel(
+        &mut self,
+        (i, j): (i64, i64),
+        pixel: &Pixel,
+        (start_x, start_y, end_x, end_y): (i64, i64, i64, i64),
+        skip: &[(i64, i64, i64, i64)],
+    ) {
+        if i < start_y || i >= end_y || j < start_x || j >= end_x {
+            return;
+        }
+        if skip.iter().any(|(left, top, right, bottom)| {
+            (*left..*right).contains(&j) && (*top..*bottom).contains(&i)
+        }) {
+            return;
+        }
+        let compositing = self.compositing;
+        let cell = &mut self.buffer[(i - start_y) as usize][(j - start_x) as usize];
+        *cell = cell.with_overlay_in(pixel, compositing);
+    }
 
-            if child.style.border.right.value != '\0'
-                && child_right + 1 >= start_x
-                && child_right + 1 < end_x
-            {
-                for i in child_top.max(start_y)..=child_bottom.min(end_y - 1) {
-                    self.buffer[(i - start_y) as usize][(child_right + 1 - start_x) as usize] =
-                        self.buffer[(i - start_y) as usize][(child_right + 1 - start_x) as usize]
-                            .with_overlay(&child.style.border.right);
-                }
-            }
+    //unlike overlay_pixel, blank cells hide what is below them
+    fn replace_pixel(
+        &mut self,
+        (i, j): (i64, i64),
+        pixel: &Pixel,
+        (start_x, start_y, end_x, end_y): (i64, i64, i64, i64),
+    ) {
+        if i < start_y || i >= end_y || j < start_x || j >= end_x {
+            return;
+        }
+        self.buffer[(i - start_y) as usize][(j - start_x) as usize] = *pixel;
+    }
 
-            if child.style.border.top_left.value != '\0'
-                && child_top - 1 >= start_y
-                && child_top - 1 < end_y
-                && child_left - 1 >= start_x
-                && child_left - 1 < end_x
-            {
-                self.buffer[(child_top - 1 - start_y) as usize]
-                    [(child_left - 1 - start_x) as usize] = self.buffer
-                    [(child_top - 1 - start_y) as usize][(child_left - 1 - start_x) as usize]
-                    .with_overlay(&child.style.border.top_left);
+    ///draws a border around the inclusive box (left, top, right, bottom)
+    fn draw_border(
+        &mut self,
+        border: &Border,
+        (left, top, right, bottom): (i64, i64, i64, i64),
+        area: (i64, i64, i64, i64),
+        skip: &[(i64, i64, i64, i64)],
+    ) {
+        let (start_x, start_y, end_x, end_y) = area;
+        let mut cells: Vec<((i64, i64), &Pixel)> = vec![
+            ((top - 1, left - 1), &border.top_left),
+            ((top - 1, right + 1), &border.top_right),
+            ((bottom + 1, right + 1), &border.bottom_right),
+            ((bottom + 1, left - 1), &border.bottom_left),
+        ];
+        for j in left.max(start_x)..=right.min(end_x - 1) {
+            cells.push(((top - 1, j), &border.top));
+            cells.push(((bottom + 1, j), &border.bottom));
+        }
+        for i in top.max(start_y)..=bottom.min(end_y - 1) {
+            cells.push(((i, left - 1), &border.left));
+            cells.push(((i, right + 1), &border.right));
+        }
+        for (position, pixel) in cells {
+            if pixel.value != '\0' {
+                self.overlay_pixel(position, pixel, area, skip);
             }
+        }
+    }
 
-            if child.style.border.top_right.value != '\0'
-                && child_top - 1 >= start_y
-                && child_top - 1 < end_y
-                && child_right + 1 >= start_x
-                && child_right + 1 < end_x
-            {
-                self.buffer[(child_top - 1 - start_y) as usize]
-                    [(child_right + 1 - start_x) as usize] = self.buffer
-                    [(child_top - 1 - start_y) as usize][(child_right + 1 - start_x) as usize]
-                    .with_overlay(&child.style.border.top_right);
+    ///draws the parts of an overflow: visible child's children that stick out of it
+    fn draw_overflow(
+        &mut self,
+        child: &RendererObjectValue,
+        (child_left, child_top): (i64, i64),
+        area: (i64, i64, i64, i64),
+        skip: &[(i64, i64, i64, i64)],
+    ) {
+        let (start_x, start_y, end_x, end_y) = area;
+        for grandchild_cell in &child.painted_children() {
+            let grandchild = grandchild_cell.read().unwrap();
+            let (x, y) = child.child_position(&grandchild);
+            let (left, top) = (child_left + x, child_top + y);
+            let (width, height) = (grandchild.calculated_width, grandchild.calculated_height);
+
+            let (buffer_x, buffer_y) = grandchild.buffer_origin;
+            for i in top.max(start_y)..(top + height).min(end_y) {
+                let Some(line) = usize::try_from(i - top - buffer_y)
+                    .ok()
+                    .and_then(|row| grandchild.buffer.get(row))
+                else {
+                    continue;
+                };
+                for j in left.max(start_x)..(left + width).min(end_x) {
+                    let pixel = usize::try_from(j - left - buffer_x)
+                        .ok()
+                        .and_then(|column| line.get(column));
+                    if let Some(pixel) = pixel {
+                        self.overlay_pixel((i, j), pixel, area, skip);
+                    }
+                }
             }
+            self.draw_border(
+                &grandchild.style.border,
+                (left, top, left + width - 1, top + height - 1),
+                area,
+                skip,
+            );
 
-            if child.style.border.bottom_right.value != '\0'
-                && child_bottom + 1 >= start_y
-                && child_bottom + 1 < end_y
-                && child_right + 1 >= start_x
-                && child_right + 1 < end_x
-            {
-                self.buffer[(child_bottom + 1 - start_y) as usize]
-                    [(child_right + 1 - start_x) as usize] = self.buffer
-                    [(child_bottom + 1 - start_y) as usize]
-                    [(child_right + 1 - start_x) as usize]
-                    .with_overlay(&child.style.border.bottom_right);
-            }
-
-            if child.style.border.bottom_left.value != '\0'
-                && child_bottom + 1 >= start_y
-                && child_bottom + 1 < end_y
-                && child_left - 1 >= start_x
-                && child_left - 1 < end_x
-            {
-                self.buffer[(child_bottom + 1 - start_y) as usize]
-                    [(child_left - 1 - start_x) as usize] = self.buffer
-                    [(child_bottom + 1 - start_y) as usize]
-                    [(child_left - 1 - start_x) as usize]
-                    .with_overlay(&child.style.border.bottom_left);
+            if grandchild.style.overflow == Overflow::Visible {
+                let mut skip = skip.to_vec();
+                skip.push((left, top, left + width, top + height));
+                self.draw_overflow(&grandchild, (left, top), area, &skip);
             }
         }
     }
@@ -978,6 +1945,14 @@ impl RendererObjectValue {
         }
 
         if self.update_content {
+            self.buffer_origin = (
+                (-self.absolute_x - renderer_padding)
+                    .min(self.calculated_width)
+                    .max(0),
+                (-self.absolute_y - renderer_padding)
+                    .min(self.calculated_height)
+                    .max(0),
+            );
             for line in &mut self.buffer {
                 line.fill(Pixel {
                     value: self.default_character,
@@ -1006,6 +1981,10 @@ impl RendererObjectValue {
                 self.draw_children(renderer_padding);
             }
 
+            if self.style.scrollbar.is_some() {
+                self.draw_scrollbars(renderer_padding);
+            }
+
             self.update_content = false;
         }
 
@@ -1018,3 +1997,23 @@ impl RendererObjectValue {
         }
     }
 }
+
+///eighths of each track cell covered by the thumb, and whether the thumb starts inside the cell
+fn thumb_cells(track: i64, content: i64, scroll: i64, max_scroll: i64) -> Vec<(i64, bool)> {
+    let track_eighths = track * 8;
+    let thumb_length =
+        (track_eighths * track / content.max(1)).clamp(8.min(track_eighths), track_eighths);
+    let thumb_start = if max_scroll > 0 {
+        (track_eighths - thumb_length) * scroll.clamp(0, max_scroll) / max_scroll
+    } else {
+        0
+    };
+    let thumb_end = thumb_start + thumb_length;
+    (0..track)
+        .map(|cell| {
+            let (cell_start, cell_end) = (cell * 8, cell * 8 + 8);
+            let covered = (thumb_end.min(cell_end) - thumb_start.max(cell_start)).max(0);
+            (covered, thumb_start > cell_start)
+        })
+        .collect()
+}
@@ -1,10 +1,11 @@
 use std::sync::{Arc, RwLock};
 
 use crate::{
-    color::Color,
+    color::{Color, Compositing},
     color_area::ColorArea,
     colors::Colors,
     geometry::Dimension,
+    input::KeyEvent,
     renderer_object_style::RendererObjectStyle,
     renderer_object_value::{RendererObjectValue, UpdateValueSignaler},
 };
@@ -12,7 +13,7 @@ use crate::{
 #[derive(Clone)]
 pub struct RendererObject {
     pub(crate) value: Arc<RwLock<RendererObjectValue>>,
-    new_value: Arc<RwLock<RendererObjectValue>>,
+    pub(crate) new_value: Arc<RwLock<RendererObjectValue>>,
 }
 
 impl RendererObject {
@@ -81,6 +82,188 @@ impl RendererObject {
         self.new_value.read().unwrap().height
     }
 
+    pub fn set_min_width(&mut self, min_width: Dimension) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().min_width = min_width;
+        self
+    }
+
+    pub fn get_min_width(&self) -> Dimension {
+        self.new_value.read().unwrap().min_width
+    }
+
+    pub fn set_max_width(&mut self, max_width: Dimension) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().max_width = max_width;
+        self
+    }
+
+    pub fn get_max_width(&self) -> Dimension {
+        self.new_value.read().unwrap().max_width
+    }
+
+    pub fn set_min_height(&mut self, min_height: Dimension) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().min_height = min_height;
+        self
+    }
+
+    pub fn get_min_height(&self) -> Dimension {
+        self.new_value.read().unwrap().min_height
+    }
+
+    pub fn set_max_height(&mut self, max_height: Dimension) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().max_height = max_height;
+        self
+    }
+
+    pub fn get_max_height(&self) -> Dimension {
+        self.new_value.read().unwrap().max_height
+    }
+
+    ///distance from the parent's left edge, with a right anchor an auto width fills the space between
+    pub fn set_left(&mut self, left: Dimension) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().left = left;
+        self
+    }
+
+    pub fn get_left(&self) -> Dimension {
+        self.new_value.read().unwrap().left
+    }
+
+    pub fn set_right(&mut self, right: Dimension) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().right = right;
+        self
+    }
+
+    pub fn get_right(&self) -> Dimension {
+        self.new_value.read().unwrap().right
+    }
+
+    ///distance from the parent's top edge, with a bottom anchor an auto height fills the space between
+    pub fn set_top(&mut self, top: Dimension) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().top = top;
+        self
+    }
+
+    pub fn get_top(&self) -> Dimension {
+        self.new_value.read().unwrap().top
+    }
+
+    pub fn set_bottom(&mut self, bottom: Dimension) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().bottom = bottom;
+        self
+    }
+
+    pub fn get_bottom(&self) -> Dimension {
+        self.new_value.read().unwrap().bottom
+    }
+
+    ///hidden objects keep their place in the layout but aren't drawn or hit
+    pub fn set_visible(&mut self, visible: bool) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().visible = visible;
+        self
+    }
+
+    pub fn get_visible(&self) -> bool {
+        self.new_value.read().unwrap().visible
+    }
+
+    ///like css display: none, undisplayed objects take no space and aren't drawn or hit
+    pub fn set_displayed(&mut self, displayed: bool) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().displayed = displayed;
+        self
+    }
+
+    pub fn get_displayed(&self) -> bool {
+        self.new_value.read().unwrap().displayed
+    }
+
+    ///visual width divided by height, the auto axis is derived from the other one,
+    ///corrected by the renderer's cell aspect so squares look square
+    pub fn set_aspect_ratio(&mut self, aspect_ratio: Option<f64>) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().aspect_ratio = aspect_ratio;
+        self
+    }
+
+    pub fn get_aspect_ratio(&self) -> Option<f64> {
+        self.new_value.read().unwrap().aspect_ratio
+    }
+
     pub fn set_geometry(
         &mut self,
         (x, y, width, height): (Dimension, Dimension, Dimension, Dimension),
@@ -105,6 +288,60 @@ impl RendererObject {
         (val.x, val.y, val.width, val.height)
     }
 
+    pub fn set_scroll_x(&mut self, scroll_x: i64) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().scroll_x = scroll_x;
+        self
+    }
+
+    pub fn get_scroll_x(&self) -> i64 {
+        self.new_value.read().unwrap().scroll_x
+    }
+
+    pub fn set_scroll_y(&mut self, scroll_y: i64) -> &mut Self {
+        self.value
+            .read()
+            .unwrap()
+            .update_value_signal
+            .write()
+            .unwrap()
+            .update();
+        self.new_value.write().unwrap().scroll_y = scroll_y;
+        self
+    }
+
+    pub fn get_scroll_y(&self) -> i64 {
+        self.new_value.read().unwrap().scroll_y
+    }
+
+    ///content offset used when the overflow is Overflow::Scroll, clamped to the content when drawn
+    pub fn set_scroll(&mut self, (scroll_x, scroll_y): (i64, i64)) -> &mut Self {
+        {
+            self.value
+                .read()
+                .unwrap()
+                .update_value_signal
+                .write()
+                .unwrap()
+                .update();
+            let mut val = self.new_value.write().unwrap();
+
+            (val.scroll_x, val.scroll_y) = (scroll_x, scroll_y);
+        }
+        self
+    }
+
+    pub fn get_scroll(&self) -> (i64, i64) {
+        let val = self.new_value.read().unwrap();
+        (val.scroll_x, val.scroll_y)
+    }
+
     pub fn set_default_background_color(&mut self, color: Color) -> &mut Self {
         self.value
             .read()
@@ -473,7 +710,7 @@ impl RendererObject {
                 .update();
             let mut val = self.new_value.write().unwrap();
             color.renderer_object_index = val.colors.len();
-            val.colors.push(color.clone());
+            val.colors.push(*color);
             val.changed_colors = true;
         }
         self
@@ -610,8 +847,91 @@ impl RendererObject {
         }
         self
     }
+
+    pub fn set_focusable(&mut self, focusable: bool) -> &mut Self {
+        self.new_value.write().unwrap().focusable = focusable;
+        self
+    }
+
+    pub fn get_focusable(&self) -> bool {
+        self.new_value.read().unwrap().focusable
+    }
+
+    ///explicit tab order, objects with an index come first in ascending order, the rest follow in tree order
+    pub fn set_tab_index(&mut self, tab_index: Option<i64>) -> &mut Self {
+        self.new_value.write().unwrap().tab_index = tab_index;
+        self
+    }
+
+    pub fn get_tab_index(&self) -> Option<i64> {
+        self.new_value.read().unwrap().tab_index
+    }
+
+    ///style used instead of the regular one while the object is focused
+    pub fn set_focused_style(&mut self, style: Option<RendererObjectStyle>) -> &mut Self {
+        {
+            self.value
+                .read()
+                .unwrap()
+                .update_value_signal
+                .write()
+                .unwrap()
+                .update();
+            let mut val = self.new_value.write().unwrap();
+            val.focused_style = style;
+            val.changed_style = true;
+        }
+        self
+    }
+
+    pub fn get_focused_style(&self) -> Option<RendererObjectStyle> {
+        self.new_value.read().unwrap().focused_style.clone()
+    }
+
+    pub(crate) fn set_focused(&mut self, focused: bool) -> &mut Self {
+        {
+            self.value
+                .read()
+                .unwrap()
+                .update_value_signal
+                .write()
+                .unwrap()
+                .update();
+            let mut val = self.new_value.write().unwrap();
+            val.focused = focused;
+            val.changed_style = true;
+        }
+        self
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.new_value.read().unwrap().focused
+    }
+
+    ///the handler returns true to consume the event, otherwise it bubbles up to the parent
+    pub fn set_key_handler(
+        &mut self,
+        handler: impl Fn(&mut RendererObject, &KeyEvent) -> bool + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.new_value.write().unwrap().key_handler = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn remove_key_handler(&mut self) -> &mut Self {
+        self.new_value.write().unwrap().key_handler = None;
+        self
+    }
     //end of getters/setters
 
+    pub(crate) fn from_value(value: Arc<RwLock<RendererObjectValue>>) -> RendererObject {
+        let new_value = value.read().unwrap().new_self.clone().unwrap();
+        RendererObject { value, new_value }
+    }
+
+    pub fn same_object(&self, other: &RendererObject) -> bool {
+        Arc::ptr_eq(&self.value, &other.value)
+    }
+
     pub fn new() -> RendererObject {
         let mut renderer_object_value = RendererObjectValue {
             buffer: Vec::new(),
@@ -619,10 +939,30 @@ impl RendererObject {
             y: Dimension::Auto,
             width: Dimension::Auto,
             height: Dimension::Auto,
+            min_width: Dimension::Auto,
+            max_width: Dimension::Auto,
+            min_height: Dimension::Auto,
+            max_height: Dimension::Auto,
+            left: Dimension::Auto,
+            right: Dimension::Auto,
+            top: Dimension::Auto,
+            bottom: Dimension::Auto,
+            scroll_x: 0,
+            scroll_y: 0,
+            scroll_position: (0, 0),
             calculated_width: 0,
             calculated_height: 0,
+            layout_width: None,
+            layout_height: None,
+            layout_position: None,
+            visible: true,
+            displayed: true,
+            aspect_ratio: None,
+            cell_aspect: 2.0,
+            compositing: Compositing::Srgb,
             absolute_x: 0,
             absolute_y: 0,
+            buffer_origin: (0, 0),
             parent_width: 0,
             parent_height: 0,
             renderer_width: 0,
@@ -650,6 +990,11 @@ impl RendererObject {
             changed_children: false,
             style: RendererObjectStyle::new(),
             changed_style: false,
+            focusable: false,
+            focused: false,
+            tab_index: None,
+            focused_style: None,
+            key_handler: None,
             new_self: None,
         };
         renderer_object_value.new_self = Some(Arc::new(RwLock::new(renderer_object_value.clone())));
@@ -2,6 +2,7 @@
 mod tests {
     use std::{
         io::{self, Write},
+        sync::Arc,
         thread,
         time::{Duration, Instant},
     };
@@ -9,16 +10,55 @@ mod tests {
     use terminal_size::terminal_size;
 
     use crate::{
-        color::Color,
+        color::{BlendMode, Color, ColorParseError, ColorSpace, Compositing, HueInterpolation},
         color_area::{ColorArea, ColorLayer},
         colors::Colors,
-        geometry::{Dimension, Dimensions},
+        focus::FocusManager,
+        geometry::{Dimension, DimensionExpression, DimensionParseError, Dimensions},
+        input::{
+            InputEvent, InputParser, Key, KeyEvent, KeyEventKind, Modifiers, MouseButton,
+            MouseEvent, MouseEventKind,
+        },
+        misc::{generic_dimension_calc, ResizeWatcher},
+        pixel::Pixel,
         renderer::Renderer,
         renderer_object_border::Borders,
-        renderer_object_style::{AlignmentX, AlignmentY, RendererObjectStyle},
+        renderer_object_style::{
+            AlignItems, AlignmentX, AlignmentY, Ellipsis, FlexLayout, GridLayout, GridPlacement,
+            GridTrack, JustifyContent, Layout, Overflow, Position, RendererObjectStyle, Spacing,
+            TextAlign, TextWrap,
+        },
+        renderer_object_value::RendererObjectValue,
         renderer_object_wrapper::RendererObject,
+        viewport::Viewport,
     };
 
+    //lays out the object on a screen of the given size
+    fn layout(object: &RendererObject, width: i64, height: i64, padding: i64) {
+        let mut value = object.value.write().unwrap();
+        value.update_value();
+        value.process_geometry(width, height, width, height, padding);
+    }
+
+    //where the parent places the child and its size, as (x, y, width, height)
+    fn placement(parent: &RendererObject, child: &RendererObject) -> (i64, i64, i64, i64) {
+        let parent_value = parent.value.read().unwrap();
+        let value = child.value.read().unwrap();
+        let (x, y) = parent_value.child_position(&value);
+        (x, y, value.calculated_width, value.calculated_height)
+    }
+
+    //lays out and draws the object without renderer padding, returns its buffer
+    fn render(object: &RendererObject, width: i64, height: i64) -> Vec<Vec<Pixel>> {
+        layout(object, width, height, 0);
+        object.value.write().unwrap().get_buffer(0, 0, 0).clone()
+    }
+
+    fn size(object: &RendererObject) -> (i64, i64) {
+        let value = object.value.read().unwrap();
+        (value.calculated_width, value.calculated_height)
+    }
+
     /* spellchecker: disable */
     #[test]
     fn string_colors() {
@@ -72,6 +112,275 @@ mod tests {
         );
     }
 
+    #[test]
+    fn css_color_4() {
+        let html = |html: &str| Color::from_html(html).unwrap();
+        assert_eq!(
+            html("rgb(10 20 30 / 50%)"),
+            Color::from_rgba(10, 20, 30, 0.5)
+        );
+        assert_eq!(html("rgba(100% 50% 0%)"), Color::from_rgb(255, 128, 0));
+        assert_eq!(html("rgb(1, 2, 3, 0.5)"), Color::from_rgba(1, 2, 3, 0.5));
+        assert_eq!(html("hsl(0.5turn 100% 50%)"), Colors::AQUA);
+        assert_eq!(html("hsl(200grad 100 50)"), Colors::AQUA);
+        assert_eq!(html("hsl(120 100 50)"), Colors::LIME);
+        assert_eq!(html("hwb(120 0% 0%)"), Colors::LIME);
+        assert_eq!(html("hwb(120 20 20)"), Color::from_rgb(51, 204, 51));
+        assert_eq!(html("hwb(0 60% 60%)"), Color::from_rgb(128, 128, 128));
+        assert_eq!(html("lab(54.29 80.8 69.89)"), Colors::RED);
+        assert_eq!(html("lch(54.29% 106.84 40.85deg)"), Colors::RED);
+        assert_eq!(html("oklab(0.628 0.2249 0.1258)"), Colors::RED);
+        assert_eq!(
+            html("oklch(62.8% 0.2577 29.23 / none)"),
+            Colors::RED.with_alpha(0.0)
+        );
+        assert_eq!(
+            Color::from_lab(50.0, 0.0, 0.0),
+            Color::from_rgb(119, 119, 119)
+        );
+        assert_eq!(Color::from_oklch(1.0, 0.0, 0.0), Colors::WHITE);
+        assert_eq!(html("transparent"), Colors::TRANSPARENT);
+        assert_eq!(html(" currentColor "), Colors::CURRENT_COLOR);
+        assert_ne!(html("currentColor"), Colors::INVALID);
+
+        //currentColor areas take the foreground color of the cell
+        let mut object = RendererObject::new();
+        object.set_geometry(Dimensions::pixel(0, 0, 2, 1));
+        object.set_default_background_color(Colors::NAVY);
+        object.set_default_foreground_color(Colors::RED);
+        object.set_colors(vec![
+            ColorArea::new(html("currentColor"), ColorLayer::Background),
+            ColorArea::new(html("currentColor"), ColorLayer::Foreground),
+        ]);
+        let pixel = render(&object, 2, 1)[0][0];
+        assert_eq!(
+            (pixel.background, pixel.foreground),
+            (Colors::RED, Colors::RED)
+        );
+
+        assert!(matches!(
+            Color::from_html("lab(1, 2, 3)"),
+            Err(ColorParseError::InvalidFunction { .. })
+        ));
+        assert!(matches!(
+            Color::from_html("rgb(1 2 3 4)"),
+            Err(ColorParseError::InvalidFunction { .. })
+        ));
+        assert!(matches!(
+            Color::from_html("rgb(1 2 3 /)"),
+            Err(ColorParseError::InvalidFunction { .. })
+        ));
+        assert!(matches!(
+            Color::from_html("hsl(120% 1 1)"),
+            Err(ColorParseError::InvalidComponent { .. })
+        ));
+    }
+
+    #[test]
+    fn color_spaces_and_mixing() {
+        for color in [
+            Colors::CADET_BLUE,
+            Colors::ORANGE,
+            Colors::BLACK,
+            Colors::WHITE,
+        ] {
+            let (h, s, v) = color.to_hsv();
+            assert_eq!(Color::from_hsv(h, s, v), color);
+            let (l, a, b) = color.to_lab();
+            assert_eq!(Color::from_lab(l, a, b), color);
+            let (l, c, h) = color.to_lch();
+            assert_eq!(Color::from_lch(l, c, h), color);
+            let (l, a, b) = color.to_oklab();
+            assert_eq!(Color::from_oklab(l, a, b), color);
+            let (l, c, h) = color.to_oklch();
+            assert_eq!(Color::from_oklch(l, c, h), color);
+        }
+        assert_eq!(Colors::RED.to_hsv(), (0.0, 1.0, 1.0));
+        let (lightness, _, _) = Colors::WHITE.to_oklab();
+        assert!((lightness - 1.0).abs() < 1e-6);
+
+        let mix = |space| Colors::RED.mix(Colors::BLUE, 0.5, space);
+        assert_eq!(mix(ColorSpace::Srgb), Color::from_rgb(128, 0, 128));
+        assert_eq!(mix(ColorSpace::Oklab), Color::from_rgb(140, 83, 162));
+        assert_eq!(
+            mix(ColorSpace::Hsl(HueInterpolation::Shorter)),
+            Colors::MAGENTA
+        );
+        assert_eq!(mix(ColorSpace::Hsl(HueInterpolation::Longer)), Colors::LIME);
+        let hsl = |from: f64, to: f64, interpolation| {
+            Color::from_hsl(from, 1.0, 0.5)
+                .mix(
+                    Color::from_hsl(to, 1.0, 0.5),
+                    0.5,
+                    ColorSpace::Hsl(interpolation),
+                )
+                .hue()
+        };
+        assert_eq!(hsl(350.0, 10.0, HueInterpolation::Shorter), 0.0);
+        assert_eq!(hsl(350.0, 10.0, HueInterpolation::Decreasing), 180.0);
+        assert_eq!(hsl(10.0, 350.0, HueInterpolation::Increasing), 180.0);
+        assert_eq!(hsl(10.0, 350.0, HueInterpolation::Decreasing), 0.0);
+        //a gray keeps the hue of the color it is mixed with
+        let (_, _, hue) = Colors::WHITE
+            .mix(
+                Colors::BLUE,
+                0.5,
+                ColorSpace::Oklch(HueInterpolation::Shorter),
+            )
+            .to_oklch();
+        assert!((hue - Colors::BLUE.to_oklch().2).abs() < 10.0);
+        assert_eq!(
+            Colors::RED
+                .with_alpha(0.0)
+                .mix(Colors::BLUE, 0.5, ColorSpace::Srgb),
+            Colors::BLUE.with_alpha(0.5)
+        );
+        assert_eq!(
+            Colors::RED.mix(Colors::BLUE, 0.0, ColorSpace::Lab),
+            Colors::RED
+        );
+        assert_eq!(
+            Colors::RED.mix(Colors::BLUE, 1.0, ColorSpace::Lab),
+            Colors::BLUE
+        );
+    }
+
+    #[test]
+    fn linear_compositing() {
+        let shade = Colors::BLACK.with_alpha(0.5);
+        assert_eq!(
+            Colors::WHITE.with_overlay(shade),
+            Color::from_rgb(127, 127, 127)
+        );
+        assert_eq!(
+            Colors::WHITE.with_overlay_in(shade, Compositing::Linear),
+            Color::from_rgb(187, 187, 187)
+        );
+        assert_eq!(
+            Colors::INVALID.with_overlay_in(shade, Compositing::Linear),
+            shade
+        );
+        //the tables bring every channel back unchanged
+        for channel in 0..=255 {
+            let color = Color::from_rgb(channel, channel, channel);
+            assert_eq!(
+                color.with_overlay_in(color.with_alpha(0.5), Compositing::Linear),
+                color
+            );
+        }
+
+        let mut child = RendererObject::new();
+        child.set_geometry(Dimensions::pixel(2, 0, 2, 1));
+        child.set_default_background_color(shade);
+        child.set_default_character(' ');
+        let mut container = RendererObject::new();
+        container.set_geometry(Dimensions::pixel(0, 0, 4, 1));
+        container.set_default_background_color(Colors::WHITE);
+        container.set_colors(vec![*ColorArea::new(shade, ColorLayer::Background)
+            .set_geometry(Dimensions::pixel(0, 0, 2, 1))]);
+        container.add_child(child);
+        let backgrounds = |compositing| {
+            container
+                .value
+                .write()
+                .unwrap()
+                .set_compositing(compositing);
+            render(&container, 40, 20)[0]
+                .iter()
+                .map(|pixel| pixel.background.red)
+                .collect::<Vec<u8>>()
+        };
+        assert_eq!(backgrounds(Compositing::Srgb), vec![127; 4]);
+        assert_eq!(backgrounds(Compositing::Linear), vec![187; 4]);
+    }
+
+    #[test]
+    fn blend_modes() {
+        let blend = |below: Color, over: Color, blend_mode| {
+            below.with_blend(over, blend_mode, Compositing::Srgb)
+        };
+        let gray = Color::from_rgb(128, 128, 128);
+        let dull = Color::from_rgb(100, 100, 100);
+        let warm = Color::from_rgb(200, 50, 0);
+        assert_eq!(
+            blend(gray, Colors::ORANGE, BlendMode::Normal),
+            Colors::ORANGE
+        );
+        assert_eq!(
+            blend(gray, Colors::ORANGE, BlendMode::Multiply),
+            Color::from_rgb(128, 83, 0)
+        );
+        assert_eq!(blend(warm, Colors::BLACK, BlendMode::Screen), warm);
+        assert_eq!(
+            blend(
+                Color::from_rgb(10, 20, 30),
+                Colors::WHITE,
+                BlendMode::Difference
+            ),
+            Color::from_rgb(245, 235, 225)
+        );
+        assert_eq!(
+            blend(dull, warm, BlendMode::Add),
+            Color::from_rgb(255, 150, 100)
+        );
+        assert_eq!(
+            blend(dull, warm, BlendMode::Subtract),
+            Color::from_rgb(0, 50, 100)
+        );
+        assert_eq!(
+            blend(dull, warm, BlendMode::Darken),
+            Color::from_rgb(100, 50, 0)
+        );
+        assert_eq!(
+            blend(dull, warm, BlendMode::Lighten),
+            Color::from_rgb(200, 100, 100)
+        );
+        assert_eq!(
+            blend(gray, Colors::BLACK, BlendMode::Overlay),
+            Color::from_rgb(1, 1, 1)
+        );
+        //a gray has no hue to keep, and only the luminosity below survives
+        assert_eq!(blend(gray, Colors::RED, BlendMode::Hue), gray);
+        assert_eq!(blend(gray, Colors::RED, BlendMode::Saturation), gray);
+        assert_eq!(
+            blend(Colors::BLUE, Colors::WHITE, BlendMode::Luminosity),
+            Colors::WHITE
+        );
+        assert_eq!(
+            blend(Colors::WHITE, Colors::BLUE, BlendMode::Luminosity).luminosity(),
+            blend(gray, Colors::BLUE, BlendMode::Luminosity).luminosity()
+        );
+        assert_eq!(
+            blend(gray, Colors::ORANGE.with_alpha(0.5), BlendMode::Multiply),
+            Color::from_rgb(128, 105, 64)
+        );
+        assert_eq!(blend(Colors::INVALID, warm, BlendMode::Multiply), warm);
+
+        let mut glow = RendererObject::new();
+        glow.set_geometry(Dimensions::pixel(2, 0, 2, 1));
+        glow.set_default_character(' ');
+        glow.set_default_background_color(Colors::WHITE.with_alpha(0.5));
+        glow.set_style(
+            RendererObjectStyle::new()
+                .set_blend_mode(BlendMode::Screen)
+                .clone(),
+        );
+        let mut sea = RendererObject::new();
+        sea.set_geometry(Dimensions::pixel(0, 0, 4, 1));
+        sea.set_default_background_color(Colors::NAVY);
+        sea.set_colors(vec![*ColorArea::new(gray, ColorLayer::Background)
+            .set_geometry(Dimensions::pixel(0, 0, 1, 1))
+            .set_blend_mode(BlendMode::Multiply)]);
+        sea.add_child(glow);
+        let backgrounds: Vec<Color> = render(&sea, 40, 20)[0]
+            .iter()
+            .map(|pixel| pixel.background)
+            .collect();
+        assert_eq!(backgrounds[0], Color::from_rgb(0, 0, 64));
+        assert_eq!(backgrounds[1], Colors::NAVY);
+        assert_eq!(backgrounds[2], Color::from_rgb(128, 128, 192));
+    }
+
     #[test]
     fn color_setters() {
         assert_eq!(
@@ -161,6 +470,1071 @@ mod tests {
         );
         assert_eq!(Dimension::from_html(" 10 ").unwrap(), Dimension::Pixel(10));
         assert_eq!(Dimension::from_html(" auto ").unwrap(), Dimension::Auto);
+
+        assert_eq!(
+            Dimension::from_html("calc(100% - 2px)").unwrap(),
+            Dimension::calc(DimensionExpression::Sub(
+                Box::new(DimensionExpression::Value(Dimension::Percent(100.0))),
+                Box::new(DimensionExpression::Value(Dimension::Pixel(2))),
+            ))
+        );
+        assert!(Dimension::from_html("calc(100% - )").is_err());
+        assert!(Dimension::from_html("calc(100% - 2px").is_err());
+        assert!(Dimension::from_html("clamp(1px, 2px)").is_err());
+
+        let calc = |html: &str, parent_width: i64| {
+            generic_dimension_calc(
+                &Dimension::from_html(html).unwrap(),
+                parent_width,
+                10,
+                80,
+                24,
+                true,
+            )
+        };
+        assert_eq!(calc("calc(100% - 2px)", 30), 28);
+        assert_eq!(calc("CALC(50vw + 3)", 30), 43);
+        assert_eq!(calc("calc((100% - 4) / 3 * 2)", 31), 18);
+        assert_eq!(calc("calc(-1 * 5px)", 30), -5);
+        assert_eq!(calc("min(50%, 20px)", 30), 15);
+        assert_eq!(calc("calc(max(10px, 1ph) + min(1px, 2px, 3px))", 30), 11);
+        assert_eq!(calc("clamp(20px, 50%, 100px)", 30), 20);
+        assert_eq!(calc("calc(1px / 0)", 30), 0);
+        assert!(Dimension::from_html("calc(100% - 2px)")
+            .unwrap()
+            .is_parent_relative());
+        assert!(!Dimension::from_html("calc(50vw + 3)")
+            .unwrap()
+            .is_parent_relative());
+
+        assert_eq!(GridTrack::from_html(" 2fr"), Ok(GridTrack::Fr(2.0)));
+        assert_eq!(
+            GridTrack::from_html("10px"),
+            Ok(GridTrack::Size(Dimension::Pixel(10)))
+        );
+        assert!(GridTrack::from_html("xfr").is_err());
+        assert!(GridTrack::from_html("inffr").is_err());
+        assert!(GridTrack::from_html("NaNfr").is_err());
+        assert!(GridTrack::from_html("-1fr").is_err());
+    }
+
+    #[test]
+    fn css_round_trip() {
+        assert_eq!(Dimension::Pixel(12).to_css(), "12px");
+        assert_eq!(Dimension::VW(50.0).to_string(), "50vw");
+        assert_eq!(Dimension::Percent(10.5).to_css(), "10.5%");
+        for html in [
+            "auto",
+            "-3px",
+            "0.25pmin",
+            "calc(100% - 2px)",
+            "calc((100% - 4) / 3 * 2)",
+            "calc(1px - (2px - 3px))",
+            "calc(-1 * 5px - -2vh)",
+            "calc(-(1px + 2px))",
+            "clamp(20px, 50%, 100px)",
+            "calc(max(10px, 1ph) + min(1px, 2px, 3px))",
+        ] {
+            let dimension = Dimension::from_html(html).unwrap();
+            assert_eq!(Dimension::from_html(&dimension.to_css()), Ok(dimension));
+        }
+        assert_eq!(
+            Dimension::from_html("CALC( (1PX+2px) )").unwrap().to_css(),
+            "calc(1px + 2px)"
+        );
+
+        let color = Color::from_rgba(95, 158, 160, 0.5);
+        assert_eq!(color.to_css(), "#5f9ea080");
+        assert_eq!(color.to_string(), "#5f9ea080");
+        assert_eq!(color.to_css_rgba(), "rgba(95, 158, 160, 0.502)");
+        assert_eq!(Colors::LIME.to_css_hsl(), "hsl(120, 100%, 50%)");
+        for color in [
+            color,
+            Colors::CADET_BLUE,
+            Colors::TRANSPARENT,
+            Colors::INVALID,
+            Colors::CURRENT_COLOR,
+        ] {
+            for css in [color.to_css(), color.to_css_rgba(), color.to_css_hsl()] {
+                assert_eq!(Color::from_html(&css), Ok(color));
+            }
+        }
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            Dimension::from_html(" 1x5 % "),
+            Err(DimensionParseError::InvalidNumber {
+                input: " 1x5 % ".to_string(),
+                span: 1..4,
+                expected: "a number before %",
+            })
+        );
+        let error = Dimension::from_html("calc(100% - 2px").unwrap_err();
+        assert!(matches!(
+            error,
+            DimensionParseError::UnexpectedCharacter { .. }
+        ));
+        assert_eq!(error.span(), 15..15);
+        assert_eq!(
+            error.to_string(),
+            "unexpected character in dimension \"calc(100% - 2px\" at 15..15: found \"end of input\", expected ')'"
+        );
+        let error = Dimension::from_html("calc(1px + foo(2px))").unwrap_err();
+        assert!(matches!(error, DimensionParseError::UnknownFunction { .. }));
+        assert_eq!(error.span(), 11..14);
+
+        assert_eq!(
+            Color::from_html("rgb(95, 300, 160)"),
+            Err(ColorParseError::InvalidComponent {
+                input: "rgb(95, 300, 160)".to_string(),
+                span: 8..11,
+                expected: "a number from 0 to 255 or a percentage",
+            })
+        );
+        let error = Color::from_html(" #12345 ").unwrap_err();
+        assert!(matches!(error, ColorParseError::InvalidHex { .. }));
+        assert_eq!(&error.input()[error.span()], "12345");
+        let error = Color::from_html("hsl(1, 2)").unwrap_err();
+        assert!(matches!(error, ColorParseError::InvalidFunction { .. }));
+        assert_eq!(error.expected(), "hsl(h s l [/ a]) or hsl(h, s, l[, a])");
+        assert!(matches!(
+            Color::from_html("nope"),
+            Err(ColorParseError::UnknownFormat { .. })
+        ));
+        let error = Color::from_html("foo(1 2 3)").unwrap_err();
+        assert!(matches!(error, ColorParseError::UnknownFormat { .. }));
+        assert_eq!(
+            error.expected(),
+            "a color name, #RRGGBBAA, #RRGGBB, #RGBA, #RGB, \
+            rgb(r g b [/ a]), rgb(r, g, b[, a]), hsl(h s l [/ a]), hsl(h, s, l[, a]), \
+            hwb(h w b [/ a]), lab(l a b [/ a]), lch(l c h [/ a]), oklab(l a b [/ a]) or oklch(l c h [/ a])"
+        );
+        let _: &dyn std::error::Error = &error;
+    }
+
+    #[test]
+    fn input_parsing() {
+        let mut parser = InputParser::new();
+        assert_eq!(
+            parser.parse(b"a\x1b[1;5A\x1b[3~\x1bOP\x01"),
+            vec![
+                InputEvent::Key(KeyEvent::new(Key::Char('a'), Modifiers::NONE)),
+                InputEvent::Key(KeyEvent::new(
+                    Key::Up,
+                    Modifiers {
+                        control: true,
+                        ..Modifiers::NONE
+                    }
+                )),
+                InputEvent::Key(KeyEvent::new(Key::Delete, Modifiers::NONE)),
+                InputEvent::Key(KeyEvent::new(Key::F(1), Modifiers::NONE)),
+                InputEvent::Key(KeyEvent::new(
+                    Key::Char('a'),
+                    Modifiers {
+                        control: true,
+                        ..Modifiers::NONE
+                    }
+                )),
+            ]
+        );
+
+        //split sequences are kept until complete
+        assert_eq!(parser.parse(b"\x1b[<0;10"), vec![]);
+        assert_eq!(
+            parser.parse(b";5M\x1b[<32;11;5M\x1b[<0;11;5m\x1b[<65;1;1M\x1b[<35;2;3M"),
+            vec![
+                InputEvent::Mouse(MouseEvent {
+                    kind: MouseEventKind::Press,
+                    button: MouseButton::Left,
+                    x: 9,
+                    y: 4,
+                    modifiers: Modifiers::NONE,
+                }),
+                InputEvent::Mouse(MouseEvent {
+                    kind: MouseEventKind::Drag,
+                    button: MouseButton::Left,
+                    x: 10,
+                    y: 4,
+                    modifiers: Modifiers::NONE,
+                }),
+                InputEvent::Mouse(MouseEvent {
+                    kind: MouseEventKind::Release,
+                    button: MouseButton::Left,
+                    x: 10,
+                    y: 4,
+                    modifiers: Modifiers::NONE,
+                }),
+                InputEvent::Mouse(MouseEvent {
+                    kind: MouseEventKind::ScrollDown,
+                    button: MouseButton::None,
+                    x: 0,
+                    y: 0,
+                    modifiers: Modifiers::NONE,
+                }),
+                InputEvent::Mouse(MouseEvent {
+                    kind: MouseEventKind::Move,
+                    button: MouseButton::None,
+                    x: 1,
+                    y: 2,
+                    modifiers: Modifiers::NONE,
+                }),
+            ]
+        );
+
+        assert_eq!(parser.parse(b"\x1b"), vec![]);
+        assert_eq!(
+            parser.flush(),
+            vec![InputEvent::Key(KeyEvent::new(Key::Escape, Modifiers::NONE))]
+        );
+
+        //the reply to the CSI 16 t cell size query is kept instead of returned
+        assert_eq!(parser.parse(b"\x1b[6;20;10t"), vec![]);
+        assert_eq!(parser.cell_size(), Some((10, 20)));
+    }
+
+    #[test]
+    fn kitty_keyboard_parsing() {
+        let control = Modifiers {
+            control: true,
+            ..Modifiers::NONE
+        };
+        let mut parser = InputParser::new();
+        assert_eq!(parser.keyboard_enhancement(), None);
+        assert_eq!(
+            parser
+                .parse(b"\x1b[?3u\x1b[105;5u\x1b[9u\x1b[97;1:2u\x1b[97;1:3u\x1b[1;5:3A\x1b[57399u"),
+            vec![
+                InputEvent::Key(KeyEvent::new(Key::Char('i'), control)),
+                InputEvent::Key(KeyEvent::new(Key::Tab, Modifiers::NONE)),
+                InputEvent::Key(
+                    KeyEvent::new(Key::Char('a'), Modifiers::NONE).with_kind(KeyEventKind::Repeat)
+                ),
+                InputEvent::Key(
+                    KeyEvent::new(Key::Char('a'), Modifiers::NONE).with_kind(KeyEventKind::Release)
+                ),
+                InputEvent::Key(KeyEvent::new(Key::Up, control).with_kind(KeyEventKind::Release)),
+                InputEvent::Key(KeyEvent::new(Key::Char('0'), Modifiers::NONE)),
+            ]
+        );
+        assert_eq!(parser.keyboard_enhancement(), Some(3));
+
+        //legacy encoding still decodes when the protocol isn't active
+        assert_eq!(
+            parser.parse(b"\t"),
+            vec![InputEvent::Key(KeyEvent::new(Key::Tab, Modifiers::NONE))]
+        );
+    }
+
+    #[test]
+    fn paste_and_focus_parsing() {
+        let mut parser = InputParser::new();
+        assert_eq!(
+            parser.parse(b"\x1b[I\x1b[200~hello\r"),
+            vec![InputEvent::FocusGained]
+        );
+        //incomplete pastes survive a flush
+        assert_eq!(parser.flush(), vec![]);
+        assert_eq!(
+            parser.parse(b"w\x1b[Aorld\x1b[201~\x1b[Oq"),
+            vec![
+                InputEvent::Paste("hello\nw\x1b[Aorld".to_string()),
+                InputEvent::FocusLost,
+                InputEvent::Key(KeyEvent::new(Key::Char('q'), Modifiers::NONE)),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resize_settling() {
+        let mut resize_watcher = ResizeWatcher::new(Duration::from_millis(50));
+        assert!(resize_watcher.poll().is_some());
+        assert!(!resize_watcher.settling());
+
+        unsafe {
+            libc::raise(libc::SIGWINCH);
+        }
+        resize_watcher.poll();
+        assert!(resize_watcher.settling());
+
+        thread::sleep(Duration::from_millis(60));
+        resize_watcher.poll();
+        assert!(!resize_watcher.settling());
+    }
+
+    #[test]
+    fn focus_navigation() {
+        let mut first = RendererObject::new();
+        first.set_focusable(true);
+        let mut second = RendererObject::new();
+        second.set_focusable(true);
+        let mut third = RendererObject::new();
+        third.set_focusable(true).set_tab_index(Some(1));
+        second.set_focused_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_border(Borders::DOUBLE);
+            Some(style)
+        });
+
+        let mut panel = RendererObject::new();
+        panel.set_children(vec![first.clone(), second.clone()]);
+        let mut root = RendererObject::new();
+        root.set_children(vec![panel.clone(), third.clone()]);
+
+        let mut focus = FocusManager::new(&root);
+        let tab = InputEvent::Key(KeyEvent::new(Key::Tab, Modifiers::NONE));
+        let back_tab = InputEvent::Key(KeyEvent::new(Key::BackTab, Modifiers::NONE));
+        assert!(focus.handle_event(&tab));
+        assert!(third.is_focused());
+        focus.handle_event(&tab);
+        assert!(first.is_focused() && !third.is_focused());
+        focus.handle_event(&tab);
+        assert!(second.is_focused());
+        focus.handle_event(&tab);
+        assert!(third.is_focused());
+        focus.handle_event(&back_tab);
+        assert!(second.is_focused());
+
+        //the focused style replaces the regular one when drawn
+        root.value.write().unwrap().update_value();
+        assert_eq!(second.value.read().unwrap().style.border.top.value, '═');
+
+        //events bubble from the focused object to its parents until consumed
+        panel.set_key_handler(|object, event| {
+            object.set_text(&format!("{:?}", event.key));
+            event.key == Key::Enter
+        });
+        let enter = InputEvent::Key(KeyEvent::new(Key::Enter, Modifiers::NONE));
+        assert!(focus.handle_event(&enter));
+        assert_eq!(panel.get_text(), "Enter");
+        let escape = InputEvent::Key(KeyEvent::new(Key::Escape, Modifiers::NONE));
+        assert!(!focus.handle_event(&escape));
+        assert_eq!(panel.get_text(), "Escape");
+    }
+
+    #[test]
+    fn hit_testing() {
+        let mut button = RendererObject::new();
+        button.set_geometry(Dimensions::pixel(2, 1, 6, 2));
+        let mut panel = RendererObject::new();
+        panel.set_geometry(Dimensions::pixel(0, 0, 20, 10));
+        panel.set_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_external_alignment(Some(AlignmentX::Right), Some(AlignmentY::Bottom));
+            style
+        });
+        panel.add_child(button.clone());
+        let mut root = RendererObject::new();
+        root.set_geometry(Dimensions::pixel(0, 0, 40, 20));
+        root.add_child(panel.clone());
+
+        layout(&root, 40, 20, 5);
+
+        let hit = |x, y| {
+            RendererObjectValue::hit_test(&root.value, x, y)
+                .map(|value| value.read().unwrap().calculated_width)
+        };
+        assert_eq!(hit(22, 12), Some(6));
+        assert_eq!(hit(21, 12), Some(20));
+        assert_eq!(hit(5, 5), Some(40));
+        assert_eq!(hit(40, 5), None);
+
+        //the renderer adds where the root sits on screen
+        root.set_x(Dimension::Pixel(-4));
+        let mut renderer = Renderer::new();
+        renderer.resize(40, 20);
+        renderer.set_object(Some(root.clone()));
+        renderer.render_frame(&mut root.value.write().unwrap());
+        let hit = |x, y| {
+            renderer
+                .hit_test(x, y)
+                .map(|object| object.value.read().unwrap().calculated_width)
+        };
+        assert_eq!(hit(18, 12), Some(6));
+        assert_eq!(hit(17, 12), Some(20));
+        assert_eq!(hit(36, 5), None);
+    }
+
+    #[test]
+    fn flex_layout() {
+        let mut sidebar = RendererObject::new();
+        sidebar.set_width(Dimension::Pixel(5));
+        let mut content = RendererObject::new();
+        content.set_geometry(Dimensions::pixel(0, 0, 5, 3));
+        content.set_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_flex(1.0, 1.0);
+            style
+        });
+        let mut button = RendererObject::new();
+        button.set_geometry(Dimensions::pixel(0, 0, 4, 2));
+        button.set_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_border(Borders::DOUBLE);
+            style
+        });
+        let mut root = RendererObject::new();
+        root.set_geometry(Dimensions::pixel(0, 0, 30, 5));
+        root.set_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_layout(Layout::Flex(
+                *FlexLayout::row().set_gap(Dimension::Pixel(2)),
+            ));
+            style
+        });
+        root.add_child(sidebar.clone());
+        root.add_child(content.clone());
+        root.add_child(button.clone());
+
+        layout(&root, 30, 5, 5);
+        //the sidebar stretches, the content takes the free space, the border counts as size
+        assert_eq!(placement(&root, &sidebar), (0, 0, 5, 5));
+        assert_eq!(placement(&root, &content), (7, 0, 15, 3));
+        assert_eq!(placement(&root, &button), (25, 1, 4, 2));
+
+        //an auto sized container wraps its children
+        let mut auto = RendererObject::new();
+        auto.set_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_layout(Layout::Flex(
+                *FlexLayout::column().set_gap(Dimension::Pixel(1)),
+            ));
+            style
+        });
+        auto.add_child(button.clone());
+        auto.add_child(content.clone());
+        layout(&auto, 30, 5, 5);
+        assert_eq!(size(&auto), (6, 8));
+
+        let mut flex_layout = FlexLayout::row();
+        flex_layout
+            .set_justify_content(JustifyContent::SpaceBetween)
+            .set_align_items(AlignItems::Center)
+            .set_wrap(true);
+        let item = crate::layout::LayoutItem {
+            width: 4,
+            height: 2,
+            grow: 0.0,
+            shrink: 1.0,
+            stretch_width: false,
+            stretch_height: false,
+        };
+        let placements = crate::layout::flex(&flex_layout, 10, 6, 0, &[item; 3]);
+        let positions: Vec<(i64, i64)> = placements.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(positions, vec![(0, 0), (6, 0), (0, 2)]);
+
+        //weights that are not finite take no space instead of panicking
+        assert_eq!(
+            crate::layout::distribute(10, &[f64::NAN, 1.0, f64::INFINITY]),
+            vec![0, 10, 0]
+        );
+        assert_eq!(
+            crate::layout::distribute(10, &[f64::MAX, f64::MAX]),
+            vec![5, 5]
+        );
+    }
+
+    #[test]
+    fn grid_layout() {
+        let mut header = RendererObject::new();
+        header.set_height(Dimension::Pixel(2));
+        header.set_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_grid_placement(Some(*GridPlacement::new(0, 0).set_span(1, 3)));
+            style
+        });
+        let sidebar = RendererObject::new();
+        let main = RendererObject::new();
+        let mut widget = RendererObject::new();
+        widget.set_geometry(Dimensions::pixel(0, 0, 4, 3));
+        widget.set_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_grid_placement(Some(GridPlacement::new(1, 2)));
+            style
+        });
+        let mut root = RendererObject::new();
+        root.set_geometry(Dimensions::pixel(0, 0, 40, 12));
+        root.set_style({
+            let mut style = RendererObjectStyle::new();
+            let mut grid = GridLayout::new(
+                vec![
+                    GridTrack::Size(Dimension::Pixel(10)),
+                    GridTrack::Fr(1.0),
+                    GridTrack::Fr(2.0),
+                ],
+                vec![GridTrack::Size(Dimension::Auto), GridTrack::Fr(1.0)],
+            );
+            grid.set_gap(Dimension::Pixel(1), Dimension::Pixel(0));
+            style.set_layout(Layout::Grid(grid));
+            style
+        });
+        root.add_child(header.clone());
+        root.add_child(widget.clone());
+        root.add_child(sidebar.clone());
+        root.add_child(main.clone());
+
+        layout(&root, 40, 12, 5);
+        assert_eq!(placement(&root, &header), (0, 0, 40, 2));
+        //children without a placement take the free cells in order
+        assert_eq!(placement(&root, &sidebar), (0, 2, 10, 10));
+        assert_eq!(placement(&root, &main), (11, 2, 9, 10));
+        assert_eq!(placement(&root, &widget), (21, 2, 4, 3));
+    }
+
+    #[test]
+    fn auto_size_from_children() {
+        let mut bar = RendererObject::new();
+        bar.set_geometry(Dimensions::pixel(0, 1, 12, 2));
+        let mut panel = RendererObject::new();
+        panel.add_child(bar.clone());
+        layout(&panel, 40, 20, 0);
+        //each axis grows with the same axis of the child
+        assert_eq!(size(&panel), (12, 3));
+        bar.set_height(Dimension::from_html("calc(1px + 3px)").unwrap());
+        layout(&panel, 40, 20, 0);
+        assert_eq!(size(&panel), (12, 5));
+    }
+
+    #[test]
+    fn size_constraints() {
+        let mut panel = RendererObject::new();
+        panel.set_geometry(Dimensions::percent(0.0, 0.0, 50.0, 100.0));
+        panel.set_min_width(Dimension::Pixel(20));
+        panel.set_max_height(Dimension::from_html("calc(100% - 2)").unwrap());
+        let mut label = RendererObject::new();
+        label.set_text("hello");
+        label.set_min_width(Dimension::Pixel(8));
+        label.set_max_width(Dimension::Pixel(4));
+        let mut root = RendererObject::new();
+        root.set_geometry(Dimensions::pixel(0, 0, 30, 10));
+        root.add_child(panel.clone());
+        root.add_child(label.clone());
+
+        let mut resize = |width: i64| {
+            root.set_width(Dimension::Pixel(width));
+            layout(&root, width, 10, 5);
+        };
+        resize(30);
+        assert_eq!(size(&panel), (20, 8));
+        resize(100);
+        assert_eq!(size(&panel), (50, 8));
+        panel.set_max_width(Dimension::Pixel(40));
+        resize(100);
+        assert_eq!(size(&panel), (40, 8));
+        //the min width wins over the max width
+        assert_eq!(size(&label), (8, 1));
+    }
+
+    #[test]
+    fn margin_and_padding() {
+        let mut badge = RendererObject::new();
+        badge.set_geometry(Dimensions::pixel(0, 0, 4, 2));
+        badge.set_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_margin(Spacing::new(
+                Dimension::Pixel(0),
+                Dimension::Pixel(2),
+                Dimension::Pixel(1),
+                Dimension::Pixel(0),
+            ));
+            style
+        });
+        let mut label = RendererObject::new();
+        label.set_text("abc");
+        label.set_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_padding(Spacing::symmetric(Dimension::Pixel(1), Dimension::Pixel(2)));
+            style
+        });
+        let mut panel = RendererObject::new();
+        panel.set_geometry(Dimensions::pixel(0, 0, 20, 10));
+        panel.set_style({
+            let mut style = RendererObjectStyle::new();
+            style
+                .set_padding(Spacing::all(Dimension::Pixel(1)))
+                .set_internal_alignment(AlignmentX::Right, AlignmentY::Bottom);
+            style
+        });
+        panel.add_child(badge.clone());
+        panel.add_child(label.clone());
+
+        layout(&panel, 20, 10, 5);
+        assert_eq!(placement(&panel, &badge), (13, 6, 4, 2));
+        //auto sizing wraps the text in its padding
+        assert_eq!(placement(&panel, &label), (12, 6, 7, 3));
+
+        let mut buffer = label.value.write().unwrap();
+        let text: String = buffer.get_buffer(0, 0, 0)[1]
+            .iter()
+            .take(7)
+            .map(|pixel| pixel.value)
+            .collect();
+        assert_eq!(text, "\0\0abc\0\0");
+    }
+
+    #[test]
+    fn overflow_modes() {
+        let line = |object: &RendererObject, row: usize| -> String {
+            render(object, 10, 5)[row]
+                .iter()
+                .map(|pixel| pixel.value)
+                .collect()
+        };
+
+        let mut marker = RendererObject::new();
+        marker.set_geometry(Dimensions::pixel(4, 0, 2, 1));
+        marker.set_default_character('x');
+        let mut child = RendererObject::new();
+        child.set_geometry(Dimensions::pixel(0, 0, 3, 3));
+        child.set_default_character('.');
+        child.add_child(marker.clone());
+        let mut parent = RendererObject::new();
+        parent.set_geometry(Dimensions::pixel(0, 0, 10, 5));
+        parent.set_default_character(' ');
+        parent.add_child(child.clone());
+
+        assert_eq!(line(&parent, 0), "...       ");
+        child.set_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_overflow(Overflow::Visible);
+            style
+        });
+        assert_eq!(line(&parent, 0), "... xx    ");
+
+        let mut scrolled = RendererObject::new();
+        scrolled.set_geometry(Dimensions::pixel(0, 0, 5, 2));
+        scrolled.set_text("abcdefgh\nijklmnop\nqrstuvwx");
+        scrolled.set_scroll((2, 1));
+        assert_eq!(line(&scrolled, 0), "abcde");
+        scrolled.set_style({
+            let mut style = RendererObjectStyle::new();
+            style.set_overflow(Overflow::Scroll);
+            style
+        });
+        assert_eq!(line(&scrolled, 0), "klmno");
+        assert_eq!(scrolled.get_scroll(), (2, 1));
+
+        scrolled.add_child(marker.clone());
+        let scrolled_value = scrolled.value.read().unwrap();
+        assert_eq!(
+            scrolled_value.child_position(&marker.value.read().unwrap()),
+            (2, -1)
+        );
+    }
+
+    #[test]
+    fn viewport_scrolling() {
+        let scrollbar_column = |object: &RendererObject| -> String {
+            render(object, 10, 10)
+                .iter()
+                .take(4)
+                .map(|row| row[4].value)
+                .collect()
+        };
+
+        let mut viewport = Viewport::new(Dimension::Pixel(5), Dimension::Pixel(4));
+        let mut items: Vec<RendererObject> = Vec::new();
+        for i in 0..10 {
+            let mut item = RendererObject::new();
+            item.set_geometry(Dimensions::pixel(0, i, 4, 1));
+            item.set_text(&i.to_string());
+            viewport.add_child(item.clone());
+            items.push(item);
+        }
+        let object = viewport.get_object();
+
+        assert_eq!(scrollbar_column(&object), "█▄  ");
+        viewport.page_down();
+        assert_eq!(viewport.get_scroll(), (0, 3));
+        viewport.scroll_by((5, 10));
+        assert_eq!(viewport.get_scroll(), (0, 6));
+        assert_eq!(scrollbar_column(&object), "  ▄█");
+
+        viewport.scroll_to((0, 0)).scroll_to_child(&items[8]);
+        assert_eq!(viewport.get_scroll(), (0, 5));
+        scrollbar_column(&object);
+        viewport.scroll_to_child(&items[2]);
+        assert_eq!(viewport.get_scroll(), (0, 2));
+
+        let wheel = |x| {
+            InputEvent::Mouse(MouseEvent {
+                kind: MouseEventKind::ScrollDown,
+                button: MouseButton::None,
+                x,
+                y: 0,
+                modifiers: Modifiers::NONE,
+            })
+        };
+        let hit_test = |x, y| {
+            RendererObjectValue::hit_test(&object.value, x, y).map(RendererObject::from_value)
+        };
+        assert!(viewport.handle_event(&wheel(1), hit_test));
+        assert_eq!(viewport.get_scroll(), (0, 5));
+        assert!(!viewport.handle_event(&wheel(7), hit_test));
+        assert_eq!(viewport.get_scroll(), (0, 5));
+
+        //scrolling before the content is laid out keeps the offset once it is drawn
+        let mut fresh = Viewport::new(Dimension::Pixel(5), Dimension::Pixel(4));
+        for item in &items {
+            fresh.add_child(item.clone());
+        }
+        fresh.scroll_to((0, 4));
+        scrollbar_column(&fresh.get_object());
+        assert_eq!(fresh.get_scroll(), (0, 4));
+        fresh.scroll_to((0, 100));
+        scrollbar_column(&fresh.get_object());
+        assert_eq!(fresh.get_scroll(), (0, 6));
+        assert_eq!(
+            fresh.get_object().value.read().unwrap().scroll_offset(),
+            (0, 6)
+        );
+    }
+
+    #[test]
+    fn text_wrapping() {
+        let lines = |object: &RendererObject| -> Vec<String> {
+            let buffer = render(object, 20, 10);
+            let (width, height) = size(object);
+            buffer
+                .iter()
+                .take(height as usize)
+                .map(|row| {
+                    row.iter()
+                        .take(width as usize)
+                        .map(|pixel| pixel.value)
+                        .collect::<String>()
+                        .trim_end_matches(['\0', ' '])
+                        .to_string()
+                })
+                .collect()
+        };
+        let text_style = |text_wrap: TextWrap, ellipsis: Option<Ellipsis>| {
+            RendererObjectStyle::new()
+                .set_text_wrap(text_wrap)
+                .set_ellipsis(ellipsis)
+                .clone()
+        };
+
+        let mut object = RendererObject::new();
+        object.set_text("the quick brown fox\nabcdefghij");
+        object.set_max_width(Dimension::Pixel(7));
+        assert_eq!(lines(&object), vec!["the qui", "abcdefg"]);
+
+        object.set_style(text_style(TextWrap::Char, None));
+        assert_eq!(
+            lines(&object),
+            vec!["the qui", "ck brow", "n fox", "abcdefg", "hij"]
+        );
+
+        object.set_style(text_style(TextWrap::Word, None));
+        assert_eq!(
+            lines(&object),
+            vec!["the", "quick", "brown", "fox", "abcdefg", "hij"]
+        );
+
+        object.set_style(text_style(TextWrap::None, Some(Ellipsis::End)));
+        assert_eq!(lines(&object), vec!["the qu…", "abcdef…"]);
+        object.set_style(text_style(TextWrap::None, Some(Ellipsis::Start)));
+        assert_eq!(lines(&object), vec!["…wn fox", "…efghij"]);
+        object.set_style(text_style(TextWrap::None, Some(Ellipsis::Middle)));
+        assert_eq!(lines(&object), vec!["the…fox", "abc…hij"]);
+    }
+
+    #[test]
+    fn text_justification() {
+        let lines = |object: &RendererObject| -> Vec<String> {
+            let buffer = render(object, 20, 10);
+            let (_, height) = size(object);
+            buffer
+                .iter()
+                .take(height as usize)
+                .map(|row| row.iter().take(10).map(|pixel| pixel.value).collect())
+                .collect()
+        };
+
+        let mut object = RendererObject::new();
+        object.set_default_character('.');
+        object.set_width(Dimension::Pixel(10));
+        object.set_text("a bb ccc dd e f\nlast");
+        object.set_style(
+            RendererObjectStyle::new()
+                .set_text_wrap(TextWrap::Word)
+                .set_text_align(Some(TextAlign::Justify))
+                .clone(),
+        );
+        assert_eq!(
+            lines(&object),
+            vec!["a  bb  ccc", "dd e f....", "last......"]
+        );
+
+        object.set_style(
+            RendererObjectStyle::new()
+                .set_text_align(Some(TextAlign::Right))
+                .clone(),
+        );
+        object.set_text("ab\nc");
+        assert_eq!(lines(&object), vec!["........ab", ".........c"]);
+
+        object.set_style(RendererObjectStyle::new().set_tab_width(4).clone());
+        object.set_text("a\tb\nabcd\te");
+        assert_eq!(lines(&object), vec!["a   b.....", "abcd    e."]);
+    }
+
+    #[test]
+    fn intrinsic_sizing() {
+        let measured = |root: &RendererObject, object: &RendererObject| {
+            layout(root, 40, 20, 0);
+            size(object)
+        };
+
+        //a percent child contributes its content size and then resolves against the result
+        let mut half = RendererObject::new();
+        half.set_width(Dimension::Percent(50.0));
+        half.set_text("abcdefghij");
+        let mut root = RendererObject::new();
+        root.add_child(half.clone());
+        assert_eq!(measured(&root, &root), (10, 1));
+        assert_eq!(measured(&root, &half), (5, 1));
+
+        //nested auto objects around a full width child
+        let mut full = RendererObject::new();
+        full.set_geometry(Dimensions::percent(0.0, 0.0, 100.0, 100.0));
+        full.set_text("abcdef\nghijkl");
+        let mut middle = RendererObject::new();
+        middle.add_child(full.clone());
+        let mut root = RendererObject::new();
+        root.add_child(middle.clone());
+        assert_eq!(measured(&root, &root), (6, 2));
+        assert_eq!(measured(&root, &full), (6, 2));
+
+        //percent offsets and limits are ignored while measuring, fixed siblings still count
+        let mut offset = RendererObject::new();
+        offset.set_geometry(Dimensions::percent(50.0, 0.0, 100.0, 100.0));
+        offset.set_max_width(Dimension::Percent(10.0));
+        offset.set_text("abcd");
+        let mut fixed = RendererObject::new();
+        fixed.set_geometry(Dimensions::pixel(0, 1, 6, 2));
+        let mut root = RendererObject::new();
+        root.add_child(offset.clone());
+        root.add_child(fixed.clone());
+        assert_eq!(measured(&root, &root), (6, 3));
+        assert_eq!(measured(&root, &offset), (1, 3));
+        fixed.set_width(Dimension::Pixel(2));
+        assert_eq!(measured(&root, &root), (4, 3));
+
+        //a flex row measures percent items by their content
+        let mut item = RendererObject::new();
+        item.set_width(Dimension::Percent(100.0));
+        item.set_text("abc");
+        let mut other = RendererObject::new();
+        other.set_text("de");
+        let mut row = RendererObject::new();
+        row.set_style(
+            RendererObjectStyle::new()
+                .set_layout(Layout::Flex(FlexLayout::row()))
+                .clone(),
+        );
+        row.add_child(item.clone());
+        row.add_child(other.clone());
+        assert_eq!(measured(&row, &row), (5, 1));
+    }
+
+    #[test]
+    fn edge_anchors() {
+        let mut corner = RendererObject::new();
+        corner.set_geometry(Dimensions::pixel(0, 0, 4, 2));
+        corner.set_right(Dimension::Pixel(2));
+        corner.set_bottom(Dimension::Pixel(0));
+        let mut stretched = RendererObject::new();
+        stretched.set_left(Dimension::Pixel(1));
+        stretched.set_right(Dimension::Pixel(3));
+        stretched.set_top(Dimension::Percent(10.0));
+        stretched.set_bottom(Dimension::Percent(20.0));
+        stretched.set_style(
+            RendererObjectStyle::new()
+                .set_margin(Spacing::symmetric(Dimension::Pixel(0), Dimension::Pixel(1)))
+                .clone(),
+        );
+        let mut parent = RendererObject::new();
+        parent.set_geometry(Dimensions::pixel(0, 0, 20, 10));
+        parent.set_style(
+            RendererObjectStyle::new()
+                .set_padding(Spacing::all(Dimension::Pixel(1)))
+                .clone(),
+        );
+        parent.add_child(corner.clone());
+        parent.add_child(stretched.clone());
+
+        layout(&parent, 20, 10, 0);
+        assert_eq!(placement(&parent, &corner), (14, 8, 4, 2));
+        assert_eq!(placement(&parent, &stretched), (2, 1, 14, 7));
+
+        //the x offset still nudges an anchored object
+        corner.set_x(Dimension::Pixel(-1));
+        layout(&parent, 20, 10, 0);
+        assert_eq!(placement(&parent, &corner), (13, 8, 4, 2));
+        parent.set_width(Dimension::Pixel(30));
+        layout(&parent, 20, 10, 0);
+        assert_eq!(placement(&parent, &stretched), (2, 1, 24, 7));
+    }
+
+    #[test]
+    fn z_index_and_fixed_position() {
+        let mut below = RendererObject::new();
+        below.set_geometry(Dimensions::pixel(0, 0, 4, 1));
+        below.set_default_character('a');
+        below.set_style(RendererObjectStyle::new().set_z_index(1).clone());
+        let mut above = RendererObject::new();
+        above.set_geometry(Dimensions::pixel(2, 0, 4, 1));
+        above.set_default_character('b');
+        let mut popup = RendererObject::new();
+        popup.set_geometry(Dimensions::percent(0.0, 0.0, 50.0, 10.0));
+        popup.set_bottom(Dimension::Pixel(0));
+        popup.set_default_character('c');
+        popup.set_style(
+            RendererObjectStyle::new()
+                .set_position(Position::Fixed)
+                .clone(),
+        );
+        let mut container = RendererObject::new();
+        container.add_child(below.clone());
+        container.add_child(above.clone());
+        container.add_child(popup.clone());
+
+        let line: String = render(&container, 40, 20)[0]
+            .iter()
+            .map(|pixel| pixel.value)
+            .collect();
+        //the higher z index wins even though it was added first, the fixed popup isn't drawn here
+        assert_eq!(line, "aaaabb");
+        let hit = RendererObjectValue::hit_test(&container.value, 3, 0).unwrap();
+        assert!(Arc::ptr_eq(&hit, &below.value));
+
+        let value = container.value.read().unwrap();
+        let fixed = value.fixed_descendants();
+        assert_eq!(fixed.len(), 1);
+        assert!(Arc::ptr_eq(&fixed[0], &popup.value));
+        assert_eq!(size(&popup), (20, 2));
+        let popup_value = popup.value.read().unwrap();
+        assert_eq!(popup_value.anchored_position(40, 20, false), Some(18));
+    }
+
+    #[test]
+    fn fixed_position_off_screen() {
+        let mut popup = RendererObject::new();
+        popup.set_geometry(Dimensions::pixel(-2, 1, 4, 1));
+        popup.set_text("abcd");
+        popup.set_style(
+            RendererObjectStyle::new()
+                .set_position(Position::Fixed)
+                .clone(),
+        );
+        let mut container = RendererObject::new();
+        container.set_geometry(Dimensions::pixel(0, 0, 10, 3));
+        container.set_default_character('.');
+        container.add_child(popup.clone());
+
+        let mut renderer = Renderer::new();
+        renderer.resize(10, 3);
+        let lines = |renderer: &mut Renderer| {
+            renderer.render_frame(&mut container.value.write().unwrap());
+            renderer
+                .frame
+                .iter()
+                .map(|line| line.iter().map(|pixel| pixel.value).collect::<String>())
+                .collect::<Vec<String>>()
+        };
+        //the part left of the screen is cut off, not shifted in
+        assert_eq!(lines(&mut renderer)[1], "cd........");
+        popup.set_x(Dimension::Pixel(-3));
+        assert_eq!(lines(&mut renderer)[1], "d.........");
+        popup.set_x(Dimension::Pixel(1));
+        assert_eq!(lines(&mut renderer)[1], ".abcd.....");
+    }
+
+    #[test]
+    fn aspect_ratio() {
+        let sized = |object: &RendererObject, cell_aspect: f64| {
+            object.value.write().unwrap().set_cell_aspect(cell_aspect);
+            layout(object, 80, 40, 0);
+            size(object)
+        };
+
+        let mut square = RendererObject::new();
+        square.set_width(Dimension::Pixel(20));
+        square.set_aspect_ratio(Some(1.0));
+        assert_eq!(sized(&square, 2.0), (20, 10));
+        assert_eq!(sized(&square, 1.0), (20, 20));
+
+        let mut wide = RendererObject::new();
+        wide.set_height(Dimension::VMin(25.0));
+        wide.set_aspect_ratio(Some(2.0));
+        assert_eq!(sized(&wide, 2.0), (40, 10));
+        //both axes set leave the ratio unused
+        wide.set_width(Dimension::Pixel(5));
+        assert_eq!(sized(&wide, 2.0), (5, 10));
+
+        //a stretched child follows the width the parent gave it
+        let mut panel = RendererObject::new();
+        panel.set_left(Dimension::Pixel(0));
+        panel.set_right(Dimension::Pixel(0));
+        panel.set_aspect_ratio(Some(1.0));
+        let mut root = RendererObject::new();
+        root.set_geometry(Dimensions::pixel(0, 0, 30, 30));
+        root.add_child(panel.clone());
+        sized(&root, 2.0);
+        assert_eq!(size(&panel), (30, 15));
+    }
+
+    #[test]
+    fn visibility_and_display() {
+        let shown = |object: &RendererObject| -> (String, (i64, i64)) {
+            let buffer = render(object, 20, 10);
+            let (width, height) = size(object);
+            let text = buffer[0]
+                .iter()
+                .take(width as usize)
+                .map(|pixel| pixel.value)
+                .collect();
+            (text, (width, height))
+        };
+
+        let mut first = RendererObject::new();
+        first.set_text("ab");
+        let mut second = RendererObject::new();
+        second.set_text("cd");
+        let mut row = RendererObject::new();
+        row.set_default_character('.');
+        row.set_style(
+            RendererObjectStyle::new()
+                .set_layout(Layout::Flex(FlexLayout::row()))
+                .clone(),
+        );
+        row.add_child(first.clone());
+        row.add_child(second.clone());
+        assert_eq!(shown(&row), ("abcd".to_string(), (4, 1)));
+
+        //hidden objects keep their space
+        first.set_visible(false);
+        assert_eq!(shown(&row), ("..cd".to_string(), (4, 1)));
+        assert!(Arc::ptr_eq(
+            &RendererObjectValue::hit_test(&row.value, 0, 0).unwrap(),
+            &row.value
+        ));
+
+        //undisplayed objects give it up
+        first.set_visible(true);
+        first.set_displayed(false);
+        assert_eq!(shown(&row), ("cd".to_string(), (2, 1)));
+        assert!(Arc::ptr_eq(
+            &RendererObjectValue::hit_test(&row.value, 0, 0).unwrap(),
+            &second.value
+        ));
+        first.set_displayed(true);
+        assert_eq!(shown(&row), ("abcd".to_string(), (4, 1)));
+        assert_eq!(row.get_children().len(), 2);
     }
 
     #[test]
@@ -0,0 +1,103 @@
+use crate::renderer_object_style::{Ellipsis, TextWrap};
+
+///replaces tabs with spaces up to the next tab stop
+pub(crate) fn expand_tabs(line: &[char], tab_width: usize) -> Vec<char> {
+    let mut expanded: Vec<char> = Vec::with_capacity(line.len());
+    for &character in line {
+        if character != '\t' {
+            expanded.push(character);
+        } else if tab_width > 0 {
+            let spaces = tab_width - expanded.len() % tab_width;
+            expanded.resize(expanded.len() + spaces, ' ');
+        }
+    }
+    expanded
+}
+
+///breaks a line into lines no wider than width
+pub(crate) fn wrap_line(line: &[char], width: usize, text_wrap: TextWrap) -> Vec<Vec<char>> {
+    if width == 0 || line.len() <= width {
+        return vec![line.to_vec()];
+    }
+    match text_wrap {
+        TextWrap::None => vec![line.to_vec()],
+        TextWrap::Char => line.chunks(width).map(|chunk| chunk.to_vec()).collect(),
+        TextWrap::Word => {
+            let mut lines: Vec<Vec<char>> = Vec::new();
+            let mut start: usize = 0;
+            while line.len() - start > width {
+                //the whitespace right after a full line is a valid break too
+                match (start + 1..=start + width)
+                    .rev()
+                    .find(|&i| line[i].is_whitespace())
+                {
+                    Some(break_at) => {
+                        let end = (start..break_at)
+                            .rev()
+                            .find(|&i| !line[i].is_whitespace())
+                            .map_or(start, |i| i + 1);
+                        lines.push(line[start..end].to_vec());
+                        start = break_at;
+                        while start < line.len() && line[start].is_whitespace() {
+                            start += 1;
+                        }
+                    }
+                    None => {
+                        lines.push(line[start..start + width].to_vec());
+                        start += width;
+                    }
+                }
+            }
+            if start < line.len() {
+                lines.push(line[start..].to_vec());
+            }
+            lines
+        }
+    }
+}
+
+///cuts a line to width, replacing the removed part with '…'
+pub(crate) fn truncate_line(line: &[char], width: usize, ellipsis: Ellipsis) -> Vec<char> {
+    if line.len() <= width {
+        return line.to_vec();
+    }
+    if width == 0 {
+        return Vec::new();
+    }
+    let kept = width - 1;
+    match ellipsis {
+        Ellipsis::Start => [&['…'], &line[line.len() - kept..]].concat(),
+        Ellipsis::Middle => {
+            let head = width / 2;
+            [&line[..head], &['…'], &line[line.len() - (kept - head)..]].concat()
+        }
+        Ellipsis::End => [&line[..kept], &['…']].concat(),
+    }
+}
+
+///widens the gaps between words so the line fills width, the first gaps get the remainder
+pub(crate) fn justify_line(line: &[char], width: usize) -> Vec<char> {
+    let indent = line
+        .iter()
+        .position(|character| !character.is_whitespace())
+        .unwrap_or(line.len());
+    let words: Vec<&[char]> = line[indent..]
+        .split(|character| character.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let letters: usize = indent + words.iter().map(|word| word.len()).sum::<usize>();
+    if words.len() < 2 || letters + words.len() - 1 > width {
+        return line.to_vec();
+    }
+    let gaps = words.len() - 1;
+    let spaces = width - letters;
+    let mut justified: Vec<char> = line[..indent].to_vec();
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            let gap = spaces / gaps + (i <= spaces % gaps) as usize;
+            justified.resize(justified.len() + gap, ' ');
+        }
+        justified.extend_from_slice(word);
+    }
+    justified
+}
@@ -0,0 +1,166 @@
+use crate::{
+    colors::Colors,
+    geometry::Dimension,
+    input::{InputEvent, Key, KeyEvent, KeyEventKind, MouseEventKind},
+    renderer_object_style::{Overflow, RendererObjectStyle, Scrollbar},
+    renderer_object_value::RendererObjectValue,
+    renderer_object_wrapper::RendererObject,
+};
+
+//lines scrolled per mouse wheel step
+const WHEEL_STEP: i64 = 3;
+
+///scrolled container for content larger than itself, like long lists and logs
+#[derive(Clone)]
+pub struct Viewport {
+    object: RendererObject,
+}
+
+impl Viewport {
+    pub fn new(width: Dimension, height: Dimension) -> Viewport {
+        let mut object = RendererObject::new();
+        object
+            .set_width(width)
+            .set_height(height)
+            .set_style(
+                RendererObjectStyle::new()
+                    .set_overflow(Overflow::Scroll)
+                    .set_scrollbar(Some(Scrollbar::new(Colors::SILVER, Colors::DIM_GRAY)))
+                    .clone(),
+            )
+            .set_focusable(true)
+            .set_key_handler(handle_key);
+        Viewport { object }
+    }
+
+    pub fn get_object(&self) -> RendererObject {
+        self.object.clone()
+    }
+
+    pub fn add_child(&mut self, child: RendererObject) -> &mut Self {
+        self.object.add_child(child);
+        self
+    }
+
+    pub fn set_scrollbar(&mut self, scrollbar: Option<Scrollbar>) -> &mut Self {
+        let style = self.object.get_style().set_scrollbar(scrollbar).clone();
+        self.object.set_style(style);
+        self
+    }
+
+    ///the scroll offset, clamped to the content as it was last laid out
+    pub fn get_scroll(&self) -> (i64, i64) {
+        current_scroll(&self.object)
+    }
+
+    ///scrolls to the given offset, clamped to the content when it is drawn
+    pub fn scroll_to(&mut self, scroll: (i64, i64)) -> &mut Self {
+        self.object.set_scroll(scroll);
+        self
+    }
+
+    pub fn scroll_by(&mut self, (delta_x, delta_y): (i64, i64)) -> &mut Self {
+        let (scroll_x, scroll_y) = current_scroll(&self.object);
+        self.scroll_to((scroll_x + delta_x, scroll_y + delta_y))
+    }
+
+    pub fn page_up(&mut self) -> &mut Self {
+        let page = page_height(&self.object);
+        self.scroll_by((0, -page))
+    }
+
+    pub fn page_down(&mut self) -> &mut Self {
+        let page = page_height(&self.object);
+        self.scroll_by((0, page))
+    }
+
+    ///scrolls as little as possible to bring a descendant fully into view
+    pub fn scroll_to_child(&mut self, child: &RendererObject) -> &mut Self {
+        let (visible_width, visible_height) = self.object.value.read().unwrap().visible_size();
+        let offset = RendererObjectValue::offset_of(&self.object.value, &child.value);
+        let ((child_x, child_y), (current_x, current_y)) = match offset {
+            Some(offset) => (offset, self.object.value.read().unwrap().scroll_offset()),
+            None => return self,
+        };
+        let (child_width, child_height) = {
+            let child = child.value.read().unwrap();
+            (child.calculated_width, child.calculated_height)
+        };
+        let (scroll_x, scroll_y) = current_scroll(&self.object);
+        self.scroll_to((
+            scroll_into_view(scroll_x, child_x + current_x, child_width, visible_width),
+            scroll_into_view(scroll_y, child_y + current_y, child_height, visible_height),
+        ))
+    }
+
+    ///scrolls on mouse wheel over the viewport and on page keys, returns true if the event was consumed,
+    ///keys only reach the viewport this way when no focus manager routes them,
+    ///hit_test finds the object under the mouse, like Renderer::hit_test or RunningRenderer::hit_test
+    pub fn handle_event(
+        &mut self,
+        event: &InputEvent,
+        hit_test: impl Fn(i64, i64) -> Option<RendererObject>,
+    ) -> bool {
+        match event {
+            InputEvent::Mouse(mouse_event) => {
+                let delta = match mouse_event.kind {
+                    MouseEventKind::ScrollUp => (0, -WHEEL_STEP),
+                    MouseEventKind::ScrollDown => (0, WHEEL_STEP),
+                    MouseEventKind::ScrollLeft => (-WHEEL_STEP, 0),
+                    MouseEventKind::ScrollRight => (WHEEL_STEP, 0),
+                    _ => return false,
+                };
+                let inside = hit_test(mouse_event.x, mouse_event.y).is_some_and(|hit| {
+                    RendererObjectValue::offset_of(&self.object.value, &hit.value).is_some()
+                });
+                if inside {
+                    self.scroll_by(delta);
+                }
+                inside
+            }
+            InputEvent::Key(key_event) => handle_key(&mut self.object, key_event),
+            _ => false,
+        }
+    }
+}
+
+fn handle_key(object: &mut RendererObject, key_event: &KeyEvent) -> bool {
+    if key_event.kind == KeyEventKind::Release {
+        return false;
+    }
+    let (scroll_x, scroll_y) = current_scroll(object);
+    let page = page_height(object);
+    let scroll = match key_event.key {
+        Key::PageUp => (scroll_x, scroll_y - page),
+        Key::PageDown => (scroll_x, scroll_y + page),
+        Key::Home => (scroll_x, 0),
+        Key::End => (scroll_x, i64::MAX),
+        _ => return false,
+    };
+    object.set_scroll(scroll);
+    true
+}
+
+//keeps one line of the previous page visible
+fn page_height(object: &RendererObject) -> i64 {
+    (object.value.read().unwrap().visible_size().1 - 1).max(1)
+}
+
+fn current_scroll(object: &RendererObject) -> (i64, i64) {
+    let (scroll_x, scroll_y) = object.get_scroll();
+    let (max_scroll_x, max_scroll_y) = object.value.read().unwrap().max_scroll();
+    (
+        scroll_x.clamp(0, max_scroll_x),
+        scroll_y.clamp(0, max_scroll_y),
+    )
+}
+
+fn scroll_into_view(scroll: i64, position: i64, size: i64, visible: i64) -> i64 {
+    if position < scroll {
+        position
+    } else if position + size > scroll + visible {
+        (position + size - visible).min(position)
+    } else {
+        scroll
+    }
+}